@@ -0,0 +1,340 @@
+//! Information about a script for editors, like the definitions of variables and the values of
+//! constants.
+
+use std::collections::HashSet;
+
+use crate::ast::*;
+use crate::check::{BindingKind, Checker, Reference};
+use crate::error::*;
+use crate::eval::Evaluate;
+use crate::scope::Scope;
+use crate::value::Value;
+use crate::{Diagnostic, Severity};
+
+/// The kind of a variable, for completions and symbols.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VariableKind {
+    Variable,
+    Function,
+    Parameter,
+    /// A variable of the scope the script is evaluated in, like a built-in function.
+    Global,
+}
+
+/// A variable that can be used at some location in a script.
+#[derive(Clone, Debug)]
+pub struct Completion {
+    pub name: String,
+    pub kind: VariableKind,
+    /// The type of the value for global variables, or the kind of the variable otherwise.
+    pub detail: String,
+}
+
+/// A variable defined by a `let` statement at the top level of a script.
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: VariableKind,
+    /// The location of the whole statement.
+    pub span: Span,
+    /// The location of the name of the variable.
+    pub name_span: Span,
+}
+
+/// The result of parsing, checking and evaluating the constants of a script.
+pub struct Analysis {
+    source: String,
+    script: Option<Script>,
+    scope: Scope,
+    checker: Checker,
+    diagnostics: Vec<Diagnostic>,
+    /// The descriptions of the values of constants, by the location of their names.
+    constants: Vec<(Span, String)>,
+}
+
+impl Analysis {
+    /// Analyze a script that is evaluated in `scope`.
+    ///
+    /// The script isn't run, because it may not terminate or may read files. Only constants are
+    /// evaluated: expressions that don't call functions or import files. Besides the problems found
+    /// by [`check_script`](crate::check_script), the diagnostics include the errors of evaluating
+    /// the constants, if there are no other errors.
+    pub fn new(source: impl AsRef<str>, scope: Scope) -> Analysis {
+        let source = source.as_ref();
+        let mut checker = Checker::new(&scope);
+        let (script, mut diagnostics) = match parse_str(source) {
+            Ok(script) => {
+                checker.script(&script, source.len());
+                let diagnostics = checker.diagnostics.clone();
+                (Some(script), diagnostics)
+            }
+            Err(_) => (None, crate::check_script(source, &scope)),
+        };
+
+        let has_errors = diagnostics.iter().any(|d| d.severity == Severity::Error);
+        let constants = match (&script, has_errors) {
+            (Some(script), false) => evaluate_constants(script, &scope, &mut diagnostics),
+            _ => Vec::new(),
+        };
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+
+        Analysis {
+            source: source.to_string(),
+            script,
+            scope,
+            checker,
+            diagnostics,
+            constants,
+        }
+    }
+
+    /// Return whether the script could be parsed. If not, there are no definitions or symbols.
+    pub fn is_parsed(&self) -> bool {
+        self.script.is_some()
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Return the location of the name in the definition of the variable at `offset`, which may be
+    /// a use of the variable or the definition itself.
+    pub fn definition(&self, offset: usize) -> Option<Span> {
+        self.definition_at(offset)
+            .map(|index| self.checker.definitions[index].span)
+    }
+
+    /// Return a description of the variable at `offset` and its location. The description of a
+    /// constant at the top level of the script includes its value.
+    pub fn hover(&self, offset: usize) -> Option<(Span, String)> {
+        let reference = self.reference_at(offset);
+        if let Some(Reference {
+            name,
+            span,
+            definition: None,
+        }) = reference
+        {
+            let value = self.scope.get(name).ok()?;
+            let ty = value.value_type().to_string().to_lowercase();
+            return Some((*span, format!("{}: {}", name, ty)));
+        }
+
+        let definition = &self.checker.definitions[self.definition_at(offset)?];
+        let span = reference.map_or(definition.span, |reference| reference.span);
+        let description = match definition.kind {
+            BindingKind::Parameter => format!("parameter {}", definition.name),
+            BindingKind::Function => match self.top_level_let(definition.span) {
+                Some(stmt) => format!("function {}", self.signature(stmt)),
+                None => format!("function {}", definition.name),
+            },
+            BindingKind::Variable => match self.constant(definition.span) {
+                Some(value) => format!("{} = {}", definition.name, value),
+                None => format!("variable {}", definition.name),
+            },
+        };
+        Some((span, description))
+    }
+
+    /// Return the variables that can be used at `offset`, including global variables.
+    pub fn completions(&self, offset: usize) -> Vec<Completion> {
+        let mut completions: Vec<Completion> = Vec::new();
+
+        // inner definitions come after the outer definitions they shadow
+        for definition in self.checker.definitions.iter().rev() {
+            let (start, end) = definition.visible;
+            if offset < start
+                || offset > end
+                || completions.iter().any(|c| c.name == definition.name)
+            {
+                continue;
+            }
+            let (kind, detail) = match definition.kind {
+                BindingKind::Variable => (VariableKind::Variable, "variable"),
+                BindingKind::Function => (VariableKind::Function, "function"),
+                BindingKind::Parameter => (VariableKind::Parameter, "parameter"),
+            };
+            completions.push(Completion {
+                name: definition.name.clone(),
+                kind,
+                detail: detail.to_string(),
+            });
+        }
+
+        for (name, value) in self.scope.values_recurse() {
+            // operators and importers are stored under names that can't be written in scripts
+            if name.starts_with('/') || completions.iter().any(|c| c.name == name) {
+                continue;
+            }
+            completions.push(Completion {
+                name: name.to_string(),
+                kind: VariableKind::Global,
+                detail: value.value_type().to_string().to_lowercase(),
+            });
+        }
+
+        completions
+    }
+
+    /// Return the variables defined at the top level of the script.
+    pub fn symbols(&self) -> Vec<Symbol> {
+        let Some(script) = &self.script else {
+            return Vec::new();
+        };
+        script
+            .stmts
+            .iter()
+            .map(|Stmt::Let(stmt)| Symbol {
+                name: stmt.name.value.clone(),
+                kind: if stmt.args.is_empty() {
+                    VariableKind::Variable
+                } else {
+                    VariableKind::Function
+                },
+                span: stmt.span,
+                name_span: stmt.name.span,
+            })
+            .collect()
+    }
+
+    /// Return the index of the definition of the variable at `offset`.
+    fn definition_at(&self, offset: usize) -> Option<usize> {
+        match self.reference_at(offset) {
+            Some(reference) => reference.definition,
+            None => self
+                .checker
+                .definitions
+                .iter()
+                .position(|definition| contains(definition.span, offset)),
+        }
+    }
+
+    fn reference_at(&self, offset: usize) -> Option<&Reference> {
+        self.checker
+            .references
+            .iter()
+            .find(|reference| contains(reference.span, offset))
+    }
+
+    /// Return the statement at the top level of the script that defines the name at `span`.
+    fn top_level_let(&self, span: Span) -> Option<&LetStmt> {
+        self.script
+            .as_ref()?
+            .stmts
+            .iter()
+            .map(|Stmt::Let(stmt)| stmt)
+            .find(|stmt| stmt.name.span == span)
+    }
+
+    /// Describe the parameters of a function defined by a `let` statement, like `f(x)(y: number)`.
+    fn signature(&self, stmt: &LetStmt) -> String {
+        let mut signature = stmt.name.value.clone();
+        for args in &stmt.args {
+            signature.push_str(&self.source[args.span.start..args.span.end]);
+        }
+        signature
+    }
+
+    /// Return the description of the value of the constant whose name is at `span`.
+    fn constant(&self, span: Span) -> Option<&str> {
+        self.constants
+            .iter()
+            .find(|(name_span, _)| *name_span == span)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Evaluate the constants at the top level of a script, in order, and describe their values. The
+/// errors of evaluating them, and the final expression if it is constant, are added to
+/// `diagnostics`.
+fn evaluate_constants(
+    script: &Script,
+    scope: &Scope,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<(Span, String)> {
+    let mut runtime_error = |err: ScriptError, span: Span| {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            code: "runtime-error",
+            message: err.root_cause().to_string(),
+            span: err.span().unwrap_or(span),
+        })
+    };
+
+    // the variables whose values are known, starting with the global variables
+    let mut names: HashSet<String> = scope
+        .values_recurse()
+        .map(|(name, _)| name.to_string())
+        .collect();
+    let mut scope = scope.clone();
+    let mut constants = Vec::new();
+    for Stmt::Let(stmt) in &script.stmts {
+        if !stmt.args.is_empty() || !is_constant(&stmt.value, &names) {
+            names.remove(&stmt.name.value);
+            continue;
+        }
+        match stmt.evaluate(scope.clone()) {
+            Ok((new_scope, _)) => {
+                scope = new_scope;
+                let value = scope.get(&stmt.name.value).unwrap_or(Value::Null);
+                constants.push((stmt.name.span, describe(&value)));
+                names.insert(stmt.name.value.clone());
+            }
+            Err(err) => {
+                runtime_error(err, stmt.span);
+                names.remove(&stmt.name.value);
+            }
+        }
+    }
+
+    if is_constant(&script.expr, &names) {
+        if let Err(err) = script.expr.evaluate_value(scope) {
+            runtime_error(err, script.span);
+        }
+    }
+    constants
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Function(..) => "<function>".to_string(),
+        value => match value.to_json() {
+            Ok(json) => serde_json::to_string_pretty(&json).unwrap_or_default(),
+            Err(err) => format!("<error: {}>", err.root_cause()),
+        },
+    }
+}
+
+/// Return whether an expression can be evaluated while editing: it doesn't call functions (which
+/// may not terminate) or import files, and only uses the variables in `names`.
+fn is_constant(expr: &Expr, names: &HashSet<String>) -> bool {
+    match expr {
+        Expr::BinOp(_, Op::Pipe, _) => false,
+        Expr::BinOp(lhs, _, rhs) => is_constant(lhs, names) && is_constant(rhs, names),
+        Expr::PathAccess(expr, segment, _) => {
+            is_constant(expr, names)
+                && match segment {
+                    PathSegment::Ident(_) => true,
+                    PathSegment::Expr(index) => is_constant(index, names),
+                }
+        }
+        Expr::NullPropagate(expr) => is_constant(expr, names),
+        Expr::Object(object) => object
+            .entries
+            .iter()
+            .all(|entry| is_constant(&entry.value, names)),
+        Expr::Array(array) => array.items.iter().all(|item| is_constant(item, names)),
+        Expr::Template(template) => template.parts.iter().all(|part| match part {
+            TemplatePart::Text(_) => true,
+            TemplatePart::Placeholder(placeholder) => is_constant(&placeholder.expr, names),
+        }),
+        Expr::Lambda(_) | Expr::String(_) | Expr::Number(_) => true,
+        Expr::Ident(ident) => names.contains(&ident.value),
+        Expr::Call(..) | Expr::Block(..) | Expr::If(..) | Expr::Match(..) | Expr::Import(..) => {
+            false
+        }
+    }
+}
+
+fn contains(span: Span, offset: usize) -> bool {
+    span.start <= offset && offset <= span.end
+}
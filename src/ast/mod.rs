@@ -23,6 +23,13 @@ pub fn parse_str(s: &str) -> ParseResult<Script> {
     Ok(node)
 }
 
+pub fn parse_repl_input(s: &str) -> ParseResult<ReplInput> {
+    let mut pairs = JJayParser::parse(Rule::repl_input, s)?;
+    let node = ReplInput::parse_many(&mut pairs)?;
+    helpers::check_end(pairs)?;
+    Ok(node)
+}
+
 pub trait Node: Sized + Clone + std::fmt::Debug {
     fn can_parse(rule: &Rule) -> bool;
     fn parse(pair: Pair<Rule>) -> ParseResult<Self>;
@@ -35,6 +42,17 @@ pub trait Node: Sized + Clone + std::fmt::Debug {
     }
 }
 
+/// A node with a location in the source of a script.
+pub trait Spanned {
+    fn span(&self) -> Span;
+}
+
+impl<T: Spanned> Spanned for Box<T> {
+    fn span(&self) -> Span {
+        T::span(self)
+    }
+}
+
 impl Node for String {
     fn can_parse(_: &Rule) -> bool {
         true
@@ -73,11 +91,12 @@ impl<T: Node> Node for Option<T> {
     }
 
     fn parse_many(pairs: &mut Pairs<Rule>) -> ParseResult<Option<T>> {
-        if let Some(_) = pairs
+        if pairs
             .peek()
             .as_ref()
             .map(Pair::as_rule)
             .filter(T::can_parse)
+            .is_some()
         {
             T::parse_many(pairs).map(Some)
         } else {
@@ -101,11 +120,12 @@ impl<T: Node> Node for Vec<T> {
 
     fn parse_many(pairs: &mut Pairs<Rule>) -> ParseResult<Vec<T>> {
         let mut items = Vec::new();
-        while let Some(_) = pairs
+        while pairs
             .peek()
             .as_ref()
             .map(Pair::as_rule)
             .filter(T::can_parse)
+            .is_some()
         {
             let pair = pairs.next().unwrap();
             items.push(T::parse(pair)?)
@@ -3,6 +3,7 @@ macro_rules! node {
         #[derive(Clone, Debug)]
         pub struct $name {
             pub value: String,
+            pub span: $crate::error::Span,
         }
 
         impl $crate::ast::Node for $name {
@@ -12,8 +13,15 @@ macro_rules! node {
 
             fn parse(pair: $crate::ast::Pair<Rule>) -> $crate::error::ParseResult<Self> {
                 $crate::ast::helpers::check_rule(&pair, &$rule)?;
+                let span = $crate::error::Span::from_pest(pair.as_span());
                 let value = pair.as_str().to_string();
-                Ok(Self { value })
+                Ok(Self { value, span })
+            }
+        }
+
+        impl $crate::ast::Spanned for $name {
+            fn span(&self) -> $crate::error::Span {
+                self.span
             }
         }
     };
@@ -28,6 +36,7 @@ macro_rules! node {
             $(
                 pub $field: $field_type,
             )*
+            pub span: $crate::error::Span,
         }
 
         impl $crate::ast::Node for $name {
@@ -37,15 +46,23 @@ macro_rules! node {
 
             fn parse(pair: $crate::ast::Pair<Rule>) -> $crate::error::ParseResult<Self> {
                 $crate::ast::helpers::check_rule(&pair, &$rule)?;
+                let span = $crate::error::Span::from_pest(pair.as_span());
                 let mut pairs = pair.into_inner();
 
                 Ok(Self {
                     $(
-                        $field: node!(@parse_many $(#[$meta])* (pairs))?
-                    ),*
+                        $field: node!(@parse_many $(#[$meta])* (pairs))?,
+                    )*
+                    span,
                 })
             }
         }
+
+        impl $crate::ast::Spanned for $name {
+            fn span(&self) -> $crate::error::Span {
+                self.span
+            }
+        }
     };
 
     (enum $name:ident = $rule:path {
@@ -79,6 +96,16 @@ macro_rules! node {
                 }
             }
         }
+
+        impl $crate::ast::Spanned for $name {
+            fn span(&self) -> $crate::error::Span {
+                match self {
+                    $(
+                        Self::$variant(inner) => $crate::ast::Spanned::span(inner),
+                    )*
+                }
+            }
+        }
     };
 
     (@parse ($pair:expr)) => { $crate::ast::Node::parse($pair) };
@@ -1,12 +1,12 @@
 use pest::iterators::Pair;
 use std::collections::BTreeMap;
 
-use super::{Node, Rule};
+use super::{Node, Rule, Spanned};
 use crate::ast::helpers;
 use crate::error::*;
 use crate::eval::Evaluate;
 use crate::scope::Scope;
-use crate::value::{Function, Value};
+use crate::value::{Function, Value, ValueType};
 
 node! {
     struct Script = Rule::script {
@@ -26,6 +26,31 @@ impl Evaluate for Script {
     }
 }
 
+node! {
+    struct ReplInput = Rule::repl_input {
+        stmts: Vec<Stmt>,
+        expr: Option<Expr>,
+    }
+}
+
+impl ReplInput {
+    /// Evaluate the statements, returning the scope extended with them and the value of the
+    /// expression, if there is one.
+    pub fn evaluate(&self, mut scope: Scope) -> ScriptResult<(Scope, Option<Value>)> {
+        for stmt in &self.stmts {
+            let (s, _) = stmt.evaluate(scope)?;
+            scope = s;
+        }
+
+        let value = self
+            .expr
+            .as_ref()
+            .map(|expr| expr.evaluate_value(scope.clone()))
+            .transpose()?;
+        Ok((scope, value))
+    }
+}
+
 node! {
     struct Block = Rule::block {
         stmts: Vec<Stmt>,
@@ -77,17 +102,23 @@ impl Evaluate for LetStmt {
         } else {
             let mut args: Vec<_> = self.args.iter().collect();
 
+            let name = Some(self.name.value.as_str());
             let arg = args.pop().unwrap();
-            let mut func = Function::new_from_expr(&scope, arg.arg.clone(), self.value.clone());
+            let mut func =
+                Function::new_from_expr(&scope, name, arg.arg.clone(), self.value.clone())
+                    .map_err(|err| err.at(arg.span))?;
 
             while let Some(arg) = args.pop() {
-                func = Function::new_nested(&scope, arg.arg.clone(), func);
+                func = Function::new_nested(&scope, name, arg.arg.clone(), func)
+                    .map_err(|err| err.at(arg.span))?;
             }
 
             func.into()
         };
 
-        let scope = scope.set(&self.name.value, value)?;
+        let scope = scope
+            .set(&self.name.value, value)
+            .map_err(|err| err.at(self.name.span))?;
         Ok((scope, Value::Null))
     }
 }
@@ -108,24 +139,13 @@ node! {
 impl FnArg {
     pub fn from_ident(ident: Ident) -> FnArg {
         FnArg {
+            span: ident.span,
             name: ident,
             ty: None,
         }
     }
 }
 
-node! {
-    struct Seq = Rule::seq {
-        exprs: Vec<Expr>,
-    }
-}
-
-impl From<Vec<Expr>> for Seq {
-    fn from(exprs: Vec<Expr>) -> Seq {
-        Seq { exprs }
-    }
-}
-
 #[derive(Clone, Debug)]
 pub enum Expr {
     BinOp(Box<Expr>, Op, Box<Expr>),
@@ -136,7 +156,11 @@ pub enum Expr {
     Array(ArrayExpr),
     Lambda(LambdaExpr),
     Block(Box<Block>),
+    If(Box<IfExpr>),
+    Match(Box<MatchExpr>),
+    Import(ImportExpr),
     String(StringExpr),
+    Template(TemplateExpr),
     Number(NumberExpr),
     Ident(Ident),
 }
@@ -146,6 +170,7 @@ impl Node for Expr {
         rule == &Rule::expr
     }
 
+    #[allow(deprecated)]
     fn parse(pair: Pair<Rule>) -> ParseResult<Self> {
         use pest::prec_climber::{Assoc, Operator, PrecClimber};
 
@@ -178,8 +203,16 @@ impl Node for Expr {
                         Expr::Lambda(atom)
                     } else if let Some(atom) = <Option<Block>>::parse_many(&mut pairs)? {
                         Expr::Block(Box::new(atom))
+                    } else if let Some(atom) = <Option<IfExpr>>::parse_many(&mut pairs)? {
+                        Expr::If(Box::new(atom))
+                    } else if let Some(atom) = <Option<MatchExpr>>::parse_many(&mut pairs)? {
+                        Expr::Match(Box::new(atom))
+                    } else if let Some(atom) = <Option<ImportExpr>>::parse_many(&mut pairs)? {
+                        Expr::Import(atom)
                     } else if let Some(atom) = <Option<StringExpr>>::parse_many(&mut pairs)? {
                         Expr::String(atom)
+                    } else if let Some(atom) = <Option<TemplateExpr>>::parse_many(&mut pairs)? {
+                        Expr::Template(atom)
                     } else if let Some(atom) = <Option<NumberExpr>>::parse_many(&mut pairs)? {
                         Expr::Number(atom)
                     } else if let Some(atom) = <Option<Ident>>::parse_many(&mut pairs)? {
@@ -191,7 +224,7 @@ impl Node for Expr {
                     // parse null propagation, path segments and function argument
                     let mut expr = atom;
                     while pairs.peek().is_some() {
-                        if let Some(_) = <Option<NullPropagation>>::parse_many(&mut pairs)? {
+                        if <Option<NullPropagation>>::parse_many(&mut pairs)?.is_some() {
                             expr = Expr::NullPropagate(Box::new(expr));
                         } else if let Some(path_segment) =
                             <Option<PathSegment>>::parse_many(&mut pairs)?
@@ -213,15 +246,49 @@ impl Node for Expr {
                     Ok(expr)
                 }
 
-                rule @ _ => unreachable!("rule {:?}", rule),
+                rule => unreachable!("rule {:?}", rule),
             },
             |lhs, op, rhs| Ok(Expr::BinOp(Box::new(lhs?), Op::parse(op)?, Box::new(rhs?))),
         )
     }
 }
 
+impl Spanned for Expr {
+    fn span(&self) -> Span {
+        match self {
+            Expr::BinOp(lhs, _, rhs) => lhs.span().to(rhs.span()),
+            Expr::Call(func, args) => func.span().to(args.span),
+            Expr::PathAccess(expr, path_segment, null_propagation) => match null_propagation {
+                Some(null_propagation) => expr.span().to(null_propagation.span),
+                None => expr.span().to(path_segment.span()),
+            },
+            Expr::NullPropagate(expr) => expr.span(),
+            Expr::Object(object) => object.span,
+            Expr::Array(array) => array.span,
+            Expr::Lambda(lambda) => lambda.span,
+            Expr::Block(block) => block.span,
+            Expr::If(if_expr) => if_expr.span,
+            Expr::Match(match_expr) => match_expr.span,
+            Expr::Import(import) => import.span,
+            Expr::String(string) => string.span,
+            Expr::Template(template) => template.span,
+            Expr::Number(number) => number.span,
+            Expr::Ident(ident) => ident.span,
+        }
+    }
+}
+
 impl Evaluate for Expr {
     fn evaluate(&self, scope: Scope) -> ScriptResult<(Scope, Value)> {
+        let value = self
+            .evaluate_inner(scope.clone())
+            .map_err(|err| err.at(self.span()))?;
+        Ok((scope, value))
+    }
+}
+
+impl Expr {
+    fn evaluate_inner(&self, scope: Scope) -> ScriptResult<Value> {
         let value = match self {
             Expr::BinOp(lhs, op, rhs) => {
                 let lhs = lhs.evaluate_value(scope.clone())?;
@@ -280,12 +347,19 @@ impl Evaluate for Expr {
 
             Expr::Block(block) => block.evaluate_value(scope.clone())?,
 
+            Expr::If(if_expr) => if_expr.evaluate_value(scope.clone())?,
+
+            Expr::Match(match_expr) => match_expr.evaluate_value(scope.clone())?,
+
+            Expr::Import(import) => import.evaluate_value(scope.clone())?,
+
             Expr::Number(number) => number.decode().map(Value::Number)?,
             Expr::String(string) => string.decode().map(Value::String)?,
+            Expr::Template(template) => template.evaluate_value(scope.clone())?,
 
             Expr::Ident(ident) => scope.get(&ident.value)?,
         };
-        Ok((scope, value))
+        Ok(value)
     }
 }
 
@@ -329,20 +403,20 @@ impl Op {
 
 impl Node for Op {
     fn can_parse(rule: &Rule) -> bool {
-        match rule {
+        matches!(
+            rule,
             Rule::pipe
-            | Rule::eq
-            | Rule::ne
-            | Rule::le
-            | Rule::ge
-            | Rule::lt
-            | Rule::gt
-            | Rule::add
-            | Rule::sub
-            | Rule::mul
-            | Rule::div => true,
-            _ => false,
-        }
+                | Rule::eq
+                | Rule::ne
+                | Rule::le
+                | Rule::ge
+                | Rule::lt
+                | Rule::gt
+                | Rule::add
+                | Rule::sub
+                | Rule::mul
+                | Rule::div
+        )
     }
 
     fn parse(pair: Pair<Rule>) -> ParseResult<Self> {
@@ -359,7 +433,7 @@ impl Node for Op {
             Rule::mul => Op::Mul,
             Rule::div => Op::Div,
 
-            rule @ _ => unreachable!("rule {:?}", rule),
+            rule => unreachable!("rule {:?}", rule),
         })
     }
 }
@@ -440,18 +514,343 @@ impl Evaluate for LambdaExpr {
         let arg = args.pop().unwrap();
         let mut func = Function::new_from_expr(
             &scope,
+            None,
             Some(FnArg::from_ident(arg.clone())),
             *self.expr.clone(),
-        );
+        )?;
 
         while let Some(arg) = args.pop() {
-            func = Function::new_nested(&scope, Some(FnArg::from_ident(arg.clone())), func);
+            func = Function::new_nested(&scope, None, Some(FnArg::from_ident(arg.clone())), func)?;
         }
 
         Ok((scope, func.into()))
     }
 }
 
+node! {
+    struct IfExpr = Rule::if_expr {
+        if_: KwIf,
+        cond: Expr,
+        then_: KwThen,
+        value: Expr,
+        elifs: Vec<IfElif>,
+        else_: Option<IfElse>,
+        end: KwEnd,
+    }
+}
+
+node! {
+    struct IfElif = Rule::if_elif {
+        elif: KwElif,
+        cond: Expr,
+        then_: KwThen,
+        value: Expr,
+    }
+}
+
+node! {
+    struct IfElse = Rule::if_else {
+        else_: KwElse,
+        value: Expr,
+    }
+}
+
+impl Evaluate for IfExpr {
+    fn evaluate(&self, scope: Scope) -> ScriptResult<(Scope, Value)> {
+        let branches = std::iter::once((&self.cond, &self.value))
+            .chain(self.elifs.iter().map(|elif| (&elif.cond, &elif.value)));
+
+        for (cond, value) in branches {
+            if cond.evaluate_value(scope.clone())?.to_bool()? {
+                let value = value.evaluate_value(scope.clone())?;
+                return Ok((scope, value.simplify()));
+            }
+        }
+
+        let value = match &self.else_ {
+            Some(else_) => else_.value.evaluate_value(scope.clone())?.simplify(),
+            None => Value::Null,
+        };
+        Ok((scope, value))
+    }
+}
+
+node! {
+    struct MatchExpr = Rule::match_expr {
+        match_: KwMatch,
+        value: Expr,
+        cases: Vec<MatchCase>,
+        end: KwEnd,
+    }
+}
+
+node! {
+    struct MatchCase = Rule::match_case {
+        case: KwCase,
+        pattern: Pattern,
+        guard: Option<MatchGuard>,
+        then_: KwThen,
+        value: Expr,
+    }
+}
+
+node! {
+    struct MatchGuard = Rule::match_guard {
+        if_: KwIf,
+        cond: Expr,
+    }
+}
+
+impl Evaluate for MatchExpr {
+    fn evaluate(&self, scope: Scope) -> ScriptResult<(Scope, Value)> {
+        let value = self.value.evaluate_value(scope.clone())?.simplify();
+
+        for case in &self.cases {
+            let case_scope = match case.pattern.match_value(scope.inherit(), &value)? {
+                Some(case_scope) => case_scope,
+                None => continue,
+            };
+
+            if let Some(guard) = &case.guard {
+                if !guard.cond.evaluate_value(case_scope.clone())?.to_bool()? {
+                    continue;
+                }
+            }
+
+            let value = case.value.evaluate_value(case_scope)?;
+            return Ok((scope, value.simplify()));
+        }
+
+        Err(ScriptError::NoMatchingCase(value.value_type()))
+    }
+}
+
+node! {
+    enum Pattern = Rule::pattern {
+        Array(ArrayPattern),
+        Object(ObjectPattern),
+        Literal(LiteralPattern),
+        Binding(BindingPattern),
+    }
+}
+
+impl Pattern {
+    /// Match a value against this pattern, returning the scope extended with the bound names if the
+    /// value matches.
+    pub fn match_value(&self, scope: Scope, value: &Value) -> ScriptResult<Option<Scope>> {
+        match self {
+            Pattern::Array(pattern) => pattern.match_value(scope, value),
+            Pattern::Object(pattern) => pattern.match_value(scope, value),
+            Pattern::Literal(pattern) => {
+                let literal = pattern.evaluate_value(scope.clone())?;
+                if literal.equals(value)? {
+                    Ok(Some(scope))
+                } else {
+                    Ok(None)
+                }
+            }
+            Pattern::Binding(pattern) => pattern.match_value(scope, value),
+        }
+    }
+}
+
+node! {
+    enum LiteralPattern = Rule::pattern_literal {
+        String(StringExpr),
+        Number(NumberExpr),
+        Constant(ConstantPattern),
+    }
+}
+
+impl Evaluate for LiteralPattern {
+    fn evaluate(&self, scope: Scope) -> ScriptResult<(Scope, Value)> {
+        match self {
+            LiteralPattern::String(string) => string.evaluate(scope),
+            LiteralPattern::Number(number) => number.evaluate(scope),
+            LiteralPattern::Constant(constant) => {
+                let value = match constant.value.as_str() {
+                    "true" => Value::Boolean(true),
+                    "false" => Value::Boolean(false),
+                    "null" => Value::Null,
+                    value => unreachable!("constant {:?}", value),
+                };
+                Ok((scope, value))
+            }
+        }
+    }
+}
+
+node! {
+    struct ConstantPattern = Rule::pattern_constant
+}
+
+node! {
+    struct BindingPattern = Rule::pattern_binding {
+        name: Ident,
+        ty: Option<TypePattern>,
+    }
+}
+
+impl BindingPattern {
+    pub fn match_value(&self, scope: Scope, value: &Value) -> ScriptResult<Option<Scope>> {
+        if let Some(ty) = &self.ty {
+            let value_type = ValueType::from_name(&ty.name.value)
+                .ok_or_else(|| script_error!("Unknown type name: {}", ty.name.value))?;
+            if value.value_type() != value_type {
+                return Ok(None);
+            }
+        }
+
+        bind_pattern_name(scope, &self.name, value.clone()).map(Some)
+    }
+}
+
+node! {
+    struct TypePattern = Rule::pattern_type {
+        name: Ident,
+    }
+}
+
+node! {
+    struct RestPattern = Rule::pattern_rest {
+        name: Option<Ident>,
+    }
+}
+
+node! {
+    struct ArrayPattern = Rule::pattern_array {
+        items: Vec<Pattern>,
+        rest: Option<RestPattern>,
+    }
+}
+
+impl ArrayPattern {
+    pub fn match_value(&self, mut scope: Scope, value: &Value) -> ScriptResult<Option<Scope>> {
+        let array = match value {
+            Value::Array(array) => array,
+            _ => return Ok(None),
+        };
+
+        let length_matches = match &self.rest {
+            Some(_) => array.len() >= self.items.len(),
+            None => array.len() == self.items.len(),
+        };
+        if !length_matches {
+            return Ok(None);
+        }
+
+        for (pattern, value) in self.items.iter().zip(array) {
+            scope = match pattern.match_value(scope, value)? {
+                Some(scope) => scope,
+                None => return Ok(None),
+            };
+        }
+
+        if let Some(RestPattern {
+            name: Some(name), ..
+        }) = &self.rest
+        {
+            let rest = Value::new_array(array[self.items.len()..].iter().cloned());
+            scope = bind_pattern_name(scope, name, rest)?;
+        }
+
+        Ok(Some(scope))
+    }
+}
+
+node! {
+    struct ObjectPattern = Rule::pattern_object {
+        entries: Vec<ObjectPatternEntry>,
+        rest: Option<RestPattern>,
+    }
+}
+
+node! {
+    struct ObjectPatternEntry = Rule::pattern_object_entry {
+        key: ObjectKey,
+        pattern: Option<Pattern>,
+    }
+}
+
+impl ObjectPattern {
+    pub fn match_value(&self, mut scope: Scope, value: &Value) -> ScriptResult<Option<Scope>> {
+        let object = match value {
+            Value::Object(object) => object,
+            _ => return Ok(None),
+        };
+
+        let mut rest = object.clone();
+        for entry in &self.entries {
+            let key = entry.key.evaluate_value(scope.clone())?.to_string()?;
+            let value = match rest.remove(&key) {
+                Some(value) => value,
+                None => return Ok(None),
+            };
+
+            scope = match (&entry.pattern, &entry.key) {
+                (Some(pattern), _) => match pattern.match_value(scope, &value)? {
+                    Some(scope) => scope,
+                    None => return Ok(None),
+                },
+                (None, ObjectKey::Ident(ident)) => bind_pattern_name(scope, ident, value)?,
+                (None, ObjectKey::String(_)) => scope,
+            };
+        }
+
+        if let Some(RestPattern {
+            name: Some(name), ..
+        }) = &self.rest
+        {
+            scope = bind_pattern_name(scope, name, Value::Object(rest))?;
+        }
+
+        Ok(Some(scope))
+    }
+}
+
+fn bind_pattern_name(scope: Scope, name: &Ident, value: Value) -> ScriptResult<Scope> {
+    if name.value == "_" {
+        Ok(scope)
+    } else {
+        scope.set(&name.value, value)
+    }
+}
+
+node! {
+    struct ImportExpr = Rule::import_expr {
+        keyword: ImportKeyword,
+        path: StringExpr,
+    }
+}
+
+// the keyword of an import expression, which selects the `crate::ImportKind` of the file
+node! {
+    enum ImportKeyword = Rule::import_kind {
+        Json(KwImportJson),
+        Str(KwImportStr),
+        Script(KwImport),
+    }
+}
+
+impl ImportKeyword {
+    fn func_name(&self) -> &'static str {
+        match self {
+            ImportKeyword::Json(..) => "/import_json",
+            ImportKeyword::Str(..) => "/import_str",
+            ImportKeyword::Script(..) => "/import",
+        }
+    }
+}
+
+impl Evaluate for ImportExpr {
+    fn evaluate(&self, scope: Scope) -> ScriptResult<(Scope, Value)> {
+        let path = self.path.decode()?;
+        let func = scope.get(self.keyword.func_name())?;
+        let value = evaluate_func_call(scope.clone(), func, Value::String(path))?;
+        Ok((scope, value))
+    }
+}
+
 node! {
     struct StringExpr = Rule::string {
         value: String,
@@ -472,6 +871,106 @@ impl Evaluate for StringExpr {
     }
 }
 
+node! {
+    struct TemplateExpr = Rule::template {
+        parts: Vec<TemplatePart>,
+    }
+}
+
+node! {
+    enum TemplatePart = Rule::template_part {
+        Text(TemplateText),
+        Placeholder(TemplatePlaceholder),
+    }
+}
+
+node! {
+    struct TemplateText = Rule::template_text
+}
+
+#[derive(Clone, Debug)]
+pub struct TemplatePlaceholder {
+    pub span: Span,
+    pub expr: Box<Expr>,
+}
+
+impl Spanned for TemplatePlaceholder {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl Node for TemplatePlaceholder {
+    fn can_parse(rule: &Rule) -> bool {
+        rule == &Rule::template_placeholder
+    }
+
+    fn parse(pair: Pair<Rule>) -> ParseResult<Self> {
+        helpers::check_rule(&pair, &Rule::template_placeholder)?;
+        let span = Span::from_pest(pair.as_span());
+        let expr = helpers::into_single(pair.into_inner()).and_then(Node::parse)?;
+        Ok(TemplatePlaceholder { span, expr })
+    }
+}
+
+impl TemplateText {
+    pub fn decode(&self) -> String {
+        let mut output = String::new();
+        let mut chars = self.value.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '\\' {
+                output.push(ch);
+                continue;
+            }
+
+            match chars.next() {
+                Some('b') => output.push('\u{8}'),
+                Some('f') => output.push('\u{c}'),
+                Some('n') => output.push('\n'),
+                Some('r') => output.push('\r'),
+                Some('t') => output.push('\t'),
+                Some('u') => {
+                    // collect consecutive \u escapes, so that surrogate pairs are decoded together
+                    let mut units = vec![decode_hex4(&mut chars)];
+                    while chars.as_str().starts_with("\\u") {
+                        chars.nth(1);
+                        units.push(decode_hex4(&mut chars));
+                    }
+                    output.extend(
+                        std::char::decode_utf16(units)
+                            .map(|ch| ch.unwrap_or(std::char::REPLACEMENT_CHARACTER)),
+                    );
+                }
+                Some(ch) => output.push(ch),
+                None => unreachable!("escape at end of template text"),
+            }
+        }
+        output
+    }
+}
+
+fn decode_hex4(chars: &mut std::str::Chars) -> u16 {
+    let digits: String = chars.take(4).collect();
+    u16::from_str_radix(&digits, 16).unwrap()
+}
+
+impl Evaluate for TemplateExpr {
+    fn evaluate(&self, scope: Scope) -> ScriptResult<(Scope, Value)> {
+        let mut output = String::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Text(text) => output.push_str(&text.decode()),
+                TemplatePart::Placeholder(placeholder) => {
+                    let value = placeholder.expr.evaluate_value(scope.clone())?.simplify();
+                    let value = value.to_string().map_err(|err| err.at(placeholder.span))?;
+                    output.push_str(&value);
+                }
+            }
+        }
+        Ok((scope, Value::String(output)))
+    }
+}
+
 node! {
     struct NumberExpr = Rule::number
 }
@@ -495,3 +994,13 @@ node! {
 }
 
 node!(struct KwLet = Rule::kw_let);
+node!(struct KwIf = Rule::kw_if);
+node!(struct KwElif = Rule::kw_elif);
+node!(struct KwElse = Rule::kw_else);
+node!(struct KwThen = Rule::kw_then);
+node!(struct KwEnd = Rule::kw_end);
+node!(struct KwMatch = Rule::kw_match);
+node!(struct KwCase = Rule::kw_case);
+node!(struct KwImport = Rule::kw_import);
+node!(struct KwImportJson = Rule::kw_import_json);
+node!(struct KwImportStr = Rule::kw_import_str);
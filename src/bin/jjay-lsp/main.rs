@@ -0,0 +1,361 @@
+//! A language server for jjay scripts, speaking the Language Server Protocol over standard input
+//! and output.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::rc::Rc;
+
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as NotificationTrait, PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, Formatting, GotoDefinition, HoverRequest,
+    Request as RequestTrait,
+};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, DocumentSymbol, DocumentSymbolParams,
+    DocumentSymbolResponse, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents,
+    HoverParams, HoverProviderCapability, Location, MarkupContent, MarkupKind, NumberOrString,
+    OneOf, Position, PublishDiagnosticsParams, Range, ServerCapabilities, SymbolKind,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+};
+
+use jjay::{Analysis, FormatOptions, Importer, Scope, Severity, VariableKind};
+
+type LspResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+fn main() -> LspResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut server = Server {
+        connection: &connection,
+        documents: HashMap::new(),
+    };
+    server.run()?;
+
+    // the thread writing to standard output stops once the connection is dropped
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+/// An open document, with the analysis of its current text and of the latest text that could be
+/// parsed, which is used for completions while the document is edited.
+struct Document {
+    text: String,
+    analysis: Rc<Analysis>,
+    parsed: Option<Rc<Analysis>>,
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    documents: HashMap<Url, Document>,
+}
+
+impl<'a> Server<'a> {
+    fn run(&mut self) -> LspResult<()> {
+        for message in &self.connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let id = request.id.clone();
+                    let response = match self.handle_request(request) {
+                        Ok(result) => Response::new_ok(id, result),
+                        Err(err) => Response {
+                            id,
+                            result: None,
+                            error: Some(lsp_server::ResponseError {
+                                code: lsp_server::ErrorCode::InvalidParams as i32,
+                                message: err.to_string(),
+                                data: None,
+                            }),
+                        },
+                    };
+                    self.connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(notification) => self.handle_notification(notification)?,
+                Message::Response(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, request: Request) -> LspResult<serde_json::Value> {
+        let result = match request.method.as_str() {
+            GotoDefinition::METHOD => {
+                let (_, params) = extract::<GotoDefinitionParams>(request)?;
+                let position = params.text_document_position_params;
+                let uri = position.text_document.uri;
+                self.definition(&uri, position.position)
+                    .map(|range| GotoDefinitionResponse::Scalar(Location { uri, range }))
+                    .map(serde_json::to_value)
+                    .transpose()?
+            }
+            HoverRequest::METHOD => {
+                let (_, params) = extract::<HoverParams>(request)?;
+                let position = params.text_document_position_params;
+                self.hover(&position.text_document.uri, position.position)
+                    .map(serde_json::to_value)
+                    .transpose()?
+            }
+            Completion::METHOD => {
+                let (_, params) = extract::<CompletionParams>(request)?;
+                let position = params.text_document_position;
+                self.completion(&position.text_document.uri, position.position)
+                    .map(serde_json::to_value)
+                    .transpose()?
+            }
+            DocumentSymbolRequest::METHOD => {
+                let (_, params) = extract::<DocumentSymbolParams>(request)?;
+                self.symbols(&params.text_document.uri)
+                    .map(serde_json::to_value)
+                    .transpose()?
+            }
+            Formatting::METHOD => {
+                let (_, params) = extract::<DocumentFormattingParams>(request)?;
+                self.format(&params.text_document.uri)
+                    .map(serde_json::to_value)
+                    .transpose()?
+            }
+            method => return Err(format!("Unsupported request: {}", method).into()),
+        };
+        Ok(result.unwrap_or(serde_json::Value::Null))
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> LspResult<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let document = params.text_document;
+                self.update(document.uri, document.text)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                // documents are synchronized in full, so the last change is the whole text
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.update(params.text_document.uri, change.text)?;
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                self.publish_diagnostics(params.text_document.uri, Vec::new())?;
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Analyze the new text of a document and publish its diagnostics.
+    fn update(&mut self, uri: Url, text: String) -> LspResult<()> {
+        // scripts aren't run, so the importer only defines the import functions
+        let importer = Importer::new().with_filesystem_access(false);
+        let scope = Scope::new_default().with_importer(&importer, None);
+        let analysis = Rc::new(Analysis::new(&text, scope));
+
+        let diagnostics = analysis
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| lsp_types::Diagnostic {
+                range: range(&text, diagnostic.span),
+                severity: Some(match diagnostic.severity {
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                }),
+                code: Some(NumberOrString::String(diagnostic.code.to_string())),
+                source: Some("jjay".to_string()),
+                message: diagnostic.message.clone(),
+                ..lsp_types::Diagnostic::default()
+            })
+            .collect();
+
+        let parsed = if analysis.is_parsed() {
+            Some(analysis.clone())
+        } else {
+            self.documents
+                .get(&uri)
+                .and_then(|document| document.parsed.clone())
+        };
+        self.documents.insert(
+            uri.clone(),
+            Document {
+                text,
+                analysis,
+                parsed,
+            },
+        );
+        self.publish_diagnostics(uri, diagnostics)
+    }
+
+    fn publish_diagnostics(
+        &self,
+        uri: Url,
+        diagnostics: Vec<lsp_types::Diagnostic>,
+    ) -> LspResult<()> {
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection
+            .sender
+            .send(Message::Notification(notification))?;
+        Ok(())
+    }
+
+    fn definition(&self, uri: &Url, position: Position) -> Option<Range> {
+        let document = self.documents.get(uri)?;
+        let span = document
+            .analysis
+            .definition(offset(&document.text, position))?;
+        Some(range(&document.text, span))
+    }
+
+    fn hover(&self, uri: &Url, position: Position) -> Option<Hover> {
+        let document = self.documents.get(uri)?;
+        let (span, description) = document.analysis.hover(offset(&document.text, position))?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```\n{}\n```", description),
+            }),
+            range: Some(range(&document.text, span)),
+        })
+    }
+
+    fn completion(&self, uri: &Url, position: Position) -> Option<CompletionResponse> {
+        let document = self.documents.get(uri)?;
+        let analysis = document.parsed.as_ref().unwrap_or(&document.analysis);
+        let items = analysis
+            .completions(offset(&document.text, position))
+            .into_iter()
+            .map(|completion| CompletionItem {
+                kind: Some(match completion.kind {
+                    VariableKind::Function => CompletionItemKind::FUNCTION,
+                    VariableKind::Global if completion.detail == "function" => {
+                        CompletionItemKind::FUNCTION
+                    }
+                    _ => CompletionItemKind::VARIABLE,
+                }),
+                label: completion.name,
+                detail: Some(completion.detail),
+                ..CompletionItem::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+
+    fn symbols(&self, uri: &Url) -> Option<DocumentSymbolResponse> {
+        let document = self.documents.get(uri)?;
+        let symbols = document
+            .analysis
+            .symbols()
+            .into_iter()
+            .map(|symbol| {
+                #[allow(deprecated)]
+                DocumentSymbol {
+                    name: symbol.name,
+                    detail: None,
+                    kind: match symbol.kind {
+                        VariableKind::Function => SymbolKind::FUNCTION,
+                        _ => SymbolKind::VARIABLE,
+                    },
+                    tags: None,
+                    deprecated: None,
+                    range: range(&document.text, symbol.span),
+                    selection_range: range(&document.text, symbol.name_span),
+                    children: None,
+                }
+            })
+            .collect();
+        Some(DocumentSymbolResponse::Nested(symbols))
+    }
+
+    /// Format a document, replacing all of its text. Documents that can't be parsed are left as
+    /// they are.
+    fn format(&self, uri: &Url) -> Option<Vec<TextEdit>> {
+        let document = self.documents.get(uri)?;
+        let formatted = jjay::format_script(&document.text, FormatOptions::default()).ok()?;
+        if formatted == document.text {
+            return Some(Vec::new());
+        }
+        Some(vec![TextEdit {
+            range: Range {
+                start: Position::new(0, 0),
+                end: position(&document.text, document.text.len()),
+            },
+            new_text: formatted,
+        }])
+    }
+}
+
+fn extract<P: serde::de::DeserializeOwned>(request: Request) -> LspResult<(RequestId, P)> {
+    let method = request.method.clone();
+    Ok(request.extract(&method)?)
+}
+
+/// Convert a location in a script to a range, with columns counted in UTF-16 code units.
+fn range(text: &str, span: jjay::error::Span) -> Range {
+    Range {
+        start: position(text, span.start),
+        end: position(text, span.end),
+    }
+}
+
+fn position(text: &str, offset: usize) -> Position {
+    let before = &text[..floor_char_boundary(text, offset)];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+/// Convert a position to a byte offset in a script, clamping it to the text.
+fn offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(i) => line_start += i + 1,
+            None => return text.len(),
+        }
+    }
+
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= position.character as usize || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+fn floor_char_boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
@@ -0,0 +1,106 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use structopt::StructOpt;
+
+use jjay::error::*;
+use jjay::{Diagnostic, Scope, Severity, Value};
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "jjay check",
+    about = "Check scripts for mistakes without running them"
+)]
+pub struct CheckOptions {
+    #[structopt(help = "Write the diagnostics as a JSON array", long = "json")]
+    pub json: bool,
+
+    #[structopt(
+        help = "Declare a variable that is defined when the script runs, like with --arg or the \
+                `input` variable",
+        short = "D",
+        long = "define",
+        number_of_values = 1
+    )]
+    pub defines: Vec<String>,
+
+    #[structopt(help = "Scripts to check, or standard input if there are none")]
+    pub files: Vec<PathBuf>,
+}
+
+/// Check the scripts and print the diagnostics, returning whether there were no errors.
+pub fn run(opts: CheckOptions) -> ScriptResult<bool> {
+    let mut scope = Scope::new_default().inherit();
+    for name in &opts.defines {
+        scope = scope.set_nofail(name, Value::Null);
+    }
+
+    let files = if opts.files.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        opts.files.clone()
+    };
+
+    let mut success = true;
+    let mut json_diagnostics = Vec::new();
+    for path in &files {
+        let source = if path == Path::new("-") {
+            let mut source = String::new();
+            io::stdin().read_to_string(&mut source)?;
+            source
+        } else {
+            fs::read_to_string(path)
+                .map_err(|err| ScriptError::InFile(path.clone(), Box::new(err.into())))?
+        };
+
+        for diagnostic in jjay::check_script(&source, &scope) {
+            if diagnostic.severity == Severity::Error {
+                success = false;
+            }
+            if opts.json {
+                json_diagnostics.push(to_json(path, &source, &diagnostic));
+            } else {
+                println!(
+                    "{}:{}:{}: {}[{}]: {}",
+                    path.display(),
+                    diagnostic.span.line,
+                    diagnostic.span.column,
+                    diagnostic.severity,
+                    diagnostic.code,
+                    diagnostic.message
+                );
+            }
+        }
+    }
+
+    if opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json_diagnostics).unwrap()
+        );
+    }
+    Ok(success)
+}
+
+fn to_json(path: &Path, source: &str, diagnostic: &Diagnostic) -> serde_json::Value {
+    let span = diagnostic.span;
+    let before_end = &source[..span.end];
+    let end_line = before_end.matches('\n').count() + 1;
+    let end_column = before_end[before_end.rfind('\n').map_or(0, |i| i + 1)..]
+        .chars()
+        .count()
+        + 1;
+
+    json!({
+        "file": path.display().to_string(),
+        "severity": diagnostic.severity.to_string(),
+        "code": diagnostic.code,
+        "message": diagnostic.message,
+        "line": span.line,
+        "column": span.column,
+        "end_line": end_line,
+        "end_column": end_column,
+    })
+}
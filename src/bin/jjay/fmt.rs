@@ -0,0 +1,79 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use structopt::StructOpt;
+
+use jjay::error::*;
+use jjay::FormatOptions;
+
+#[derive(StructOpt)]
+#[structopt(name = "jjay fmt", about = "Format scripts in canonical style")]
+pub struct FmtOptions {
+    #[structopt(
+        help = "Don't write the formatted scripts, but exit with an error if any of them is not \
+                formatted",
+        long = "check"
+    )]
+    pub check: bool,
+
+    #[structopt(help = "Maximum line width", long = "width", default_value = "100")]
+    pub width: usize,
+
+    #[structopt(
+        help = "Scripts to format in place, or standard input if there are none, in which case the \
+                formatted script is written to standard output"
+    )]
+    pub files: Vec<PathBuf>,
+}
+
+/// Format the scripts, returning whether all of them could be formatted (or, with `--check`, were
+/// already formatted).
+pub fn run(opts: FmtOptions) -> ScriptResult<bool> {
+    let options = FormatOptions { width: opts.width };
+
+    if opts.files.is_empty() {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source)?;
+        let formatted = match jjay::format_script(&source, options) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                eprintln!("{}", err.render(Some(&source)));
+                return Ok(false);
+            }
+        };
+
+        if opts.check {
+            return Ok(formatted == source);
+        }
+        print!("{}", formatted);
+        return Ok(true);
+    }
+
+    let mut success = true;
+    for path in &opts.files {
+        let source = fs::read_to_string(path)
+            .map_err(|err| ScriptError::InFile(path.clone(), Box::new(err.into())))?;
+        let formatted = match jjay::format_script(&source, options) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                let err = ScriptError::InFile(path.clone(), Box::new(err));
+                eprintln!("{}", err.render(Some(&source)));
+                success = false;
+                continue;
+            }
+        };
+
+        if formatted == source {
+            continue;
+        }
+        if opts.check {
+            println!("{} is not formatted", path.display());
+            success = false;
+        } else {
+            fs::write(path, formatted)?;
+        }
+    }
+
+    Ok(success)
+}
@@ -0,0 +1,418 @@
+mod check;
+mod fmt;
+mod repl;
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use structopt::StructOpt;
+
+use jjay::error::*;
+use jjay::{CompiledScript, Importer, Scope, Value, YamlOptions};
+
+#[derive(StructOpt)]
+pub struct Options {
+    #[structopt(help = "", short = "c", long = "compact")]
+    pub compact: bool,
+
+    #[structopt(
+        help = "Add a library search path for imports",
+        short = "L",
+        long = "lib-path",
+        number_of_values = 1
+    )]
+    pub lib_paths: Vec<PathBuf>,
+
+    #[structopt(
+        help = "Disallow scripts to read files with imports or functions like read_yaml",
+        long = "no-filesystem"
+    )]
+    pub no_filesystem: bool,
+
+    #[structopt(
+        help = "Define a string variable",
+        long = "arg",
+        number_of_values = 2,
+        value_names = &["name", "value"]
+    )]
+    pub args: Vec<String>,
+
+    #[structopt(
+        help = "Define a variable from a JSON value",
+        long = "arg-json",
+        number_of_values = 2,
+        value_names = &["name", "json"]
+    )]
+    pub json_args: Vec<String>,
+
+    #[structopt(
+        help = "Define a string variable from the contents of a file",
+        long = "arg-file",
+        number_of_values = 2,
+        value_names = &["name", "path"]
+    )]
+    pub file_args: Vec<String>,
+
+    #[structopt(
+        help = "Read JSON documents from a file (or - for standard input) as input",
+        short = "i",
+        long = "input",
+        number_of_values = 1
+    )]
+    pub inputs: Vec<PathBuf>,
+
+    #[structopt(
+        help = "Apply the result of the script as a function to every input document, instead of \
+                running the script with the document as the variable `input`",
+        long = "apply"
+    )]
+    pub apply: bool,
+
+    #[structopt(
+        help = "Write the result for every input document as a single line of JSON",
+        long = "ndjson"
+    )]
+    pub ndjson: bool,
+
+    #[structopt(
+        help = "Output format",
+        short = "o",
+        long = "output",
+        default_value = "json",
+        possible_values = &["json", "yaml", "toml", "ini"]
+    )]
+    pub output: OutputFormat,
+
+    #[structopt(
+        help = "Write every item of a top-level array as a separate YAML document",
+        long = "multi-document"
+    )]
+    pub multi_document: bool,
+
+    #[structopt(
+        help = "Write every entry of the resulting object to a file in this directory, with the \
+                entry's key as the path and the format chosen by the file extension",
+        short = "m",
+        long = "multi"
+    )]
+    pub multi: Option<PathBuf>,
+
+    #[structopt(
+        help = "List the files that would be written with --multi, without writing them",
+        long = "dry-run",
+        requires = "multi"
+    )]
+    pub dry_run: bool,
+
+    #[structopt(help = "")]
+    pub file: PathBuf,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Yaml,
+    Toml,
+    Ini,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<OutputFormat, String> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            "toml" => Ok(OutputFormat::Toml),
+            "ini" => Ok(OutputFormat::Ini),
+            _ => Err(format!("unknown output format: {}", s)),
+        }
+    }
+}
+
+fn main() {
+    // subcommands are dispatched by hand, so that scripts can still be run with `jjay <file>`
+    let args: Vec<_> = std::env::args_os().collect();
+    let subcommand = args.get(1).and_then(|arg| arg.to_str());
+    if let Some(name @ ("repl" | "fmt" | "check")) = subcommand {
+        let args =
+            std::iter::once(format!("jjay {}", name).into()).chain(args[2..].iter().cloned());
+        let result = match name {
+            "repl" => repl::run(repl::ReplOptions::from_iter(args)).map(|_| true),
+            "fmt" => fmt::run(fmt::FmtOptions::from_iter(args)),
+            _ => check::run(check::CheckOptions::from_iter(args)),
+        };
+        match result {
+            Ok(true) => (),
+            Ok(false) => std::process::exit(1),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let opts = Options::from_iter(args);
+
+    let mut source = None;
+    if let Err(err) = run(opts, &mut source) {
+        eprintln!("{}", err.render(source.as_deref()));
+        std::process::exit(1);
+    }
+}
+
+fn run(opts: Options, source: &mut Option<String>) -> ScriptResult<()> {
+    let mut importer = Importer::new().with_filesystem_access(!opts.no_filesystem);
+    for path in &opts.lib_paths {
+        importer = importer.with_search_path(path);
+    }
+
+    let script_from_stdin = opts.file == Path::new("-");
+    if script_from_stdin && opts.inputs.iter().any(|path| path == Path::new("-")) {
+        return Err(ScriptError::Other(
+            "Cannot read both the script and input from standard input".to_string(),
+        ));
+    }
+    let file = if script_from_stdin {
+        None
+    } else {
+        Some(opts.file.as_path())
+    };
+
+    // define variables, which can't replace built-in functions like the ones that import files,
+    // which are resolved relative to the script
+    let mut scope = Scope::new_default()
+        .with_importer(&importer, file)
+        .inherit();
+    for arg in opts.args.chunks(2) {
+        scope = set_arg(scope, &arg[0], Value::String(arg[1].clone()))?;
+    }
+    for arg in opts.json_args.chunks(2) {
+        let json = serde_json::from_str(&arg[1]).map_err(|err| {
+            ScriptError::Other(format!("Invalid JSON for argument {}: {}", arg[0], err))
+        })?;
+        scope = set_arg(scope, &arg[0], Value::from_json(json))?;
+    }
+    for arg in opts.file_args.chunks(2) {
+        let contents = fs::read_to_string(&arg[1]).map_err(|err| {
+            ScriptError::Other(format!(
+                "Cannot read {} for argument {}: {}",
+                arg[1], arg[0], err
+            ))
+        })?;
+        scope = set_arg(scope, &arg[0], Value::String(contents))?;
+    }
+
+    // read and parse the script once, even if it runs for every input document
+    let in_file = |err: ScriptError| match file {
+        Some(file) => ScriptError::InFile(file.to_path_buf(), Box::new(err)),
+        None => err,
+    };
+    let script = match file {
+        Some(file) => fs::read_to_string(file)
+            .map_err(ScriptError::from)
+            .and_then(CompiledScript::new)
+            .map_err(in_file)?,
+        None => {
+            let mut script = String::new();
+            io::stdin().read_to_string(&mut script)?;
+            CompiledScript::new(source.insert(script))?
+        }
+    };
+    let run_script = |scope: Scope| script.run(scope).map_err(in_file);
+
+    if let Some(dir) = &opts.multi {
+        if !opts.inputs.is_empty() {
+            return Err(ScriptError::Other(
+                "Cannot write multiple files when reading input documents".to_string(),
+            ));
+        }
+        let value = run_script(scope)?;
+        return write_files(dir, &value, &opts);
+    }
+
+    if opts.inputs.is_empty() {
+        let value = run_script(scope)?;
+        return write_value(io::stdout(), &value, opts.output, &opts, opts.compact);
+    }
+
+    // transform input documents, either by applying the script's result to them or by running the
+    // script for each of them
+    let func = if opts.apply {
+        Some(run_script(scope.clone())?)
+    } else {
+        None
+    };
+    for path in &opts.inputs {
+        let reader: Box<dyn Read> = if path == Path::new("-") {
+            Box::new(io::stdin())
+        } else {
+            Box::new(BufReader::new(File::open(path)?))
+        };
+
+        for document in serde_json::Deserializer::from_reader(reader).into_iter() {
+            let document = document.map_err(|err| {
+                ScriptError::Other(format!("Invalid JSON in {}: {}", path.display(), err))
+            })?;
+            let document = Value::from_json(document);
+
+            let value = match &func {
+                Some(func) => func
+                    .invoke(Scope::new_default(), document)
+                    .map_err(in_file)?,
+                None => run_script(set_arg(scope.clone(), "input", document)?)?,
+            };
+            if opts.output == OutputFormat::Yaml && !opts.ndjson {
+                // separate the results for every document
+                println!("---");
+            }
+            write_value(
+                io::stdout(),
+                &value,
+                opts.output,
+                &opts,
+                opts.compact || opts.ndjson,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_value<W: Write>(
+    out: W,
+    value: &Value,
+    format: OutputFormat,
+    opts: &Options,
+    compact: bool,
+) -> ScriptResult<()> {
+    match format {
+        OutputFormat::Json if compact => value.write_to(out),
+        OutputFormat::Json => value.write_to_pretty(out),
+        OutputFormat::Yaml => {
+            let options = YamlOptions {
+                flow: compact,
+                multi_document: opts.multi_document,
+            };
+            value.write_yaml_to(out, options)
+        }
+        OutputFormat::Toml => value.write_toml_to(out),
+        OutputFormat::Ini => value.write_ini_to(out),
+    }
+}
+
+/// Write every entry of an object to a file in `dir`, with the format chosen by the extension of
+/// the file. All files are formatted before any of them are written, so that an error doesn't leave
+/// a partial output behind.
+fn write_files(dir: &Path, value: &Value, opts: &Options) -> ScriptResult<()> {
+    let entries = match value {
+        Value::Object(entries) => entries,
+        value => {
+            return Err(ScriptError::Other(format!(
+                "Cannot write multiple files: expected Object, found {}",
+                value.value_type()
+            )))
+        }
+    };
+
+    let mut files = Vec::new();
+    let mut names = HashMap::new();
+    for (name, value) in entries {
+        let path = Path::new(name);
+        let is_relative = path
+            .components()
+            .all(|component| matches!(component, Component::Normal(..) | Component::CurDir));
+        if name.is_empty() || !is_relative {
+            return Err(ScriptError::Other(format!(
+                "Cannot write {:?}: paths must be relative and stay within the output directory",
+                name
+            )));
+        }
+
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        let mut contents = Vec::new();
+        match (extension, value) {
+            (Some("txt"), Value::String(string)) => contents.extend_from_slice(string.as_bytes()),
+            (Some("txt"), value) => {
+                return Err(ScriptError::Other(format!(
+                    "Cannot write {}: expected String, found {}",
+                    name,
+                    value.value_type()
+                )))
+            }
+            (extension, value) => {
+                let format = match extension {
+                    Some("json") => OutputFormat::Json,
+                    Some("yaml") | Some("yml") => OutputFormat::Yaml,
+                    Some("toml") => OutputFormat::Toml,
+                    Some("ini") => OutputFormat::Ini,
+                    _ => {
+                        return Err(ScriptError::Other(format!(
+                            "Cannot write {}: unknown file extension",
+                            name
+                        )))
+                    }
+                };
+                write_value(&mut contents, value, format, opts, opts.compact)?;
+            }
+        }
+        let path: PathBuf = path
+            .components()
+            .filter(|component| matches!(component, Component::Normal(..)))
+            .collect();
+        // names like "a.json" and "./a.json" would overwrite each other
+        if let Some(other) = names.insert(path.clone(), name) {
+            return Err(ScriptError::Other(format!(
+                "Cannot write {:?}: {:?} has the same path",
+                name, other
+            )));
+        }
+        files.push((dir.join(path), contents));
+    }
+
+    if opts.dry_run {
+        for (path, contents) in &files {
+            println!("{} ({} bytes)", path.display(), contents.len());
+        }
+        return Ok(());
+    }
+
+    fs::create_dir_all(dir)?;
+    let dir = fs::canonicalize(dir)?;
+    for (path, contents) in &files {
+        // symbolic links could point outside of the output directory, so the deepest directory that
+        // already exists must be inside it, and the file itself must not be a symbolic link
+        let existing = path
+            .ancestors()
+            .skip(1)
+            .find(|ancestor| ancestor.exists())
+            .unwrap_or(&dir);
+        let is_symlink = fs::symlink_metadata(path)
+            .map(|meta| meta.file_type().is_symlink())
+            .unwrap_or(false);
+        if !fs::canonicalize(existing)?.starts_with(&dir) || is_symlink {
+            return Err(ScriptError::Other(format!(
+                "Cannot write {}: the path is outside of the output directory",
+                path.display()
+            )));
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+    }
+
+    Ok(())
+}
+
+/// Define a variable given on the command line, which may not shadow a built-in variable.
+fn set_arg(scope: Scope, name: &str, value: Value) -> ScriptResult<Scope> {
+    if scope.get(name).is_ok() {
+        return Err(ScriptError::VariableAlreadyExists(name.to_string()));
+    }
+    scope.set(name, value)
+}
@@ -0,0 +1,301 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use structopt::StructOpt;
+
+use jjay::error::*;
+use jjay::{Importer, Scope, Value};
+
+const HELP: &str = "\
+Enter statements (`let x = 1;`) and expressions to evaluate them.
+Input continues on the next line while brackets are unbalanced.
+
+Commands:
+  :load <file>  Evaluate a script file, keeping the variables it defines
+  :scope        List the variables that have been defined
+  :type <expr>  Show the type of an expression
+  :help         Show this help
+  :quit         Exit the REPL";
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "jjay repl",
+    about = "Evaluate statements and expressions interactively"
+)]
+pub struct ReplOptions {
+    #[structopt(
+        help = "Add a library search path for imports",
+        short = "L",
+        long = "lib-path",
+        number_of_values = 1
+    )]
+    pub lib_paths: Vec<PathBuf>,
+
+    #[structopt(
+        help = "Disallow scripts to read files with imports or functions like read_yaml",
+        long = "no-filesystem"
+    )]
+    pub no_filesystem: bool,
+}
+
+pub fn run(opts: ReplOptions) -> ScriptResult<()> {
+    let mut importer = Importer::new().with_filesystem_access(!opts.no_filesystem);
+    for path in &opts.lib_paths {
+        importer = importer.with_search_path(path);
+    }
+    let mut repl = Repl::new(importer);
+
+    let mut editor = DefaultEditor::new().map_err(readline_error)?;
+    // history is only kept for interactive sessions
+    let history = std::env::var_os("HOME")
+        .filter(|_| io::stdin().is_terminal())
+        .map(|home| Path::new(&home).join(".jjay_history"));
+    if let Some(history) = &history {
+        let _ = editor.load_history(history);
+    }
+
+    let mut input = String::new();
+    loop {
+        let prompt = if input.is_empty() { "> " } else { ". " };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                if !input.is_empty() {
+                    input.push('\n');
+                }
+                input.push_str(&line);
+                if is_incomplete(&input) {
+                    continue;
+                }
+
+                let input = std::mem::take(&mut input);
+                if input.trim().is_empty() {
+                    continue;
+                }
+                let _ = editor.add_history_entry(input.as_str());
+                if !repl.handle(input.trim()) {
+                    break;
+                }
+            }
+            // Ctrl-C discards the current input
+            Err(ReadlineError::Interrupted) => input.clear(),
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(readline_error(err)),
+        }
+    }
+
+    if let Some(history) = &history {
+        let _ = editor.save_history(history);
+    }
+    Ok(())
+}
+
+struct Repl {
+    importer: Importer,
+    scope: Scope,
+    builtins: HashSet<String>,
+}
+
+impl Repl {
+    fn new(importer: Importer) -> Repl {
+        let scope = Scope::new_default().with_importer(&importer, None);
+        let builtins = scope
+            .values_recurse()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        Repl {
+            scope,
+            importer,
+            builtins,
+        }
+    }
+
+    /// Handle a command or evaluate input, returning whether the REPL should continue.
+    fn handle(&mut self, input: &str) -> bool {
+        let (command, arg) = match input.strip_prefix(':') {
+            Some(command) => match command.split_once(char::is_whitespace) {
+                Some((command, arg)) => (command, arg.trim()),
+                None => (command, ""),
+            },
+            None => {
+                if let Err(err) = self.evaluate(input) {
+                    eprintln!("{}", err.render(Some(input)));
+                }
+                return true;
+            }
+        };
+
+        let result = match command {
+            "load" => self.load(Path::new(arg)),
+            "scope" => {
+                self.print_scope();
+                Ok(())
+            }
+            "type" => self.print_type(arg),
+            "help" => {
+                println!("{}", HELP);
+                Ok(())
+            }
+            "quit" => return false,
+            _ => Err(ScriptError::Other(format!(
+                "Unknown command :{}, see :help",
+                command
+            ))),
+        };
+
+        if let Err(err) = result {
+            eprintln!("{}", err.render(Some(arg)));
+        }
+        true
+    }
+
+    /// Evaluate statements and an expression. Variables may be redefined, as every input is
+    /// evaluated in a new scope that inherits the previous one.
+    fn evaluate(&mut self, input: &str) -> ScriptResult<()> {
+        let (scope, value) = jjay::run_repl_input(input, self.scope.inherit())?;
+        self.scope = scope;
+        if let Some(value) = value {
+            print_value(&value)?;
+        }
+        Ok(())
+    }
+
+    fn load(&mut self, path: &Path) -> ScriptResult<()> {
+        let source = fs::read_to_string(path).map_err(|err| {
+            ScriptError::Other(format!("Cannot read {}: {}", path.display(), err))
+        })?;
+        // imports in the file are relative to the file
+        let scope = self
+            .scope
+            .inherit()
+            .with_importer(&self.importer, Some(path));
+        let (scope, value) = jjay::run_repl_input(source, scope)
+            .map_err(|err| ScriptError::InFile(path.to_path_buf(), Box::new(err)))?;
+
+        self.scope = scope.with_importer(&self.importer, None);
+        if let Some(value) = value {
+            print_value(&value)?;
+        }
+        Ok(())
+    }
+
+    fn print_scope(&self) {
+        let mut seen = HashSet::new();
+        let mut variables: Vec<_> = self
+            .scope
+            .values_recurse()
+            .filter(|(name, _)| seen.insert(*name))
+            .filter(|(name, _)| !name.starts_with('/') && !self.builtins.contains(*name))
+            .collect();
+        variables.sort_by_key(|(name, _)| *name);
+
+        for (name, value) in variables {
+            println!("{}: {}", name, type_name(value));
+        }
+    }
+
+    fn print_type(&self, input: &str) -> ScriptResult<()> {
+        match jjay::run_repl_input(input, self.scope.inherit())? {
+            (_, Some(value)) => println!("{}", type_name(&value)),
+            (_, None) => {
+                return Err(ScriptError::Other(
+                    "Expected an expression after :type".to_string(),
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+fn print_value(value: &Value) -> ScriptResult<()> {
+    match value {
+        Value::Function(..) => {
+            println!("<function>");
+            Ok(())
+        }
+        value => value.write_to_pretty(io::stdout()),
+    }
+}
+
+fn type_name(value: &Value) -> String {
+    value.value_type().to_string().to_lowercase()
+}
+
+/// Return whether input has unbalanced brackets, or an unterminated string or comment, so that it
+/// continues on the next line.
+fn is_incomplete(input: &str) -> bool {
+    enum Frame {
+        Bracket,
+        Template,
+        Placeholder,
+    }
+
+    let mut stack = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some(Frame::Template) = stack.last() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '`' => {
+                    stack.pop();
+                }
+                '$' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    stack.push(Frame::Placeholder);
+                }
+                _ => (),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => loop {
+                match chars.next() {
+                    Some('\\') => {
+                        chars.next();
+                    }
+                    Some('"') => break,
+                    Some(_) => (),
+                    None => return true,
+                }
+            },
+            '`' => stack.push(Frame::Template),
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().find(|&c| c == '\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if prev == '*' => break,
+                        Some(c) => prev = c,
+                        None => return true,
+                    }
+                }
+            }
+            '(' | '[' | '{' => stack.push(Frame::Bracket),
+            ')' | ']' | '}' => {
+                // an unbalanced closing bracket is an error that the parser reports
+                let Some(_) = stack.pop() else {
+                    return false;
+                };
+            }
+            _ => (),
+        }
+    }
+
+    !stack.is_empty()
+}
+
+fn readline_error(err: ReadlineError) -> ScriptError {
+    match err {
+        ReadlineError::Io(err) => ScriptError::Io(err),
+        err => ScriptError::Other(err.to_string()),
+    }
+}
@@ -0,0 +1,527 @@
+//! Static checks of scripts.
+//!
+//! Identifiers are resolved against the lexical scopes of a script without evaluating it, which
+//! finds mistakes that would otherwise only be reported when the affected code runs.
+
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+
+use crate::ast::*;
+use crate::error::*;
+use crate::scope::Scope;
+use crate::value::ValueType;
+
+/// A problem found by [`check_script`].
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// A short identifier of the kind of problem, like `unused-variable`.
+    pub code: &'static str,
+    pub message: String,
+    pub span: Span,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The script fails to parse, or fails when the code is evaluated.
+    Error,
+    /// The script runs, but probably doesn't do what was intended.
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(fmt, "error"),
+            Severity::Warning => write!(fmt, "warning"),
+        }
+    }
+}
+
+/// Check a script without evaluating it, returning the problems found in the order of their
+/// location. The variables of `scope` are available to the script, like built-in functions.
+pub fn check_script(source: impl AsRef<str>, scope: &Scope) -> Vec<Diagnostic> {
+    let script = match parse_str(source.as_ref()) {
+        Ok(script) => script,
+        Err(err) => return vec![syntax_error(err)],
+    };
+
+    let mut checker = Checker::new(scope);
+    checker.script(&script, source.as_ref().len());
+
+    let mut diagnostics = checker.diagnostics;
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+    diagnostics
+}
+
+fn syntax_error(err: ParseError) -> Diagnostic {
+    let (span, message) = match &err {
+        ParseError::Pest(err) => {
+            let (start, end) = match err.location {
+                pest::error::InputLocation::Pos(pos) => (pos, pos),
+                pest::error::InputLocation::Span(span) => span,
+            };
+            let (line, column) = match err.line_col {
+                pest::error::LineColLocation::Pos(pos) => pos,
+                pest::error::LineColLocation::Span(start, _) => start,
+            };
+            let span = Span {
+                start,
+                end,
+                line,
+                column,
+            };
+            (span, err.variant.message().to_string())
+        }
+        ParseError::Other(message) => {
+            let span = Span {
+                start: 0,
+                end: 0,
+                line: 1,
+                column: 1,
+            };
+            (span, message.clone())
+        }
+    };
+
+    Diagnostic {
+        severity: Severity::Error,
+        code: "syntax-error",
+        message,
+        span,
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum BindingKind {
+    Variable,
+    Function,
+    Parameter,
+}
+
+/// A variable defined in a script.
+pub(crate) struct Definition {
+    pub name: String,
+    pub span: Span,
+    pub kind: BindingKind,
+    /// The range of the source in which the variable can be used.
+    pub visible: (usize, usize),
+    used: bool,
+}
+
+/// A use of a variable, with the index of its definition if it is defined in the script.
+pub(crate) struct Reference {
+    pub name: String,
+    pub span: Span,
+    pub definition: Option<usize>,
+}
+
+pub(crate) struct Checker {
+    globals: HashSet<String>,
+    /// The definitions of every open scope, and where the scope ends.
+    scopes: Vec<(Vec<usize>, usize)>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub definitions: Vec<Definition>,
+    pub references: Vec<Reference>,
+}
+
+impl Checker {
+    pub fn new(scope: &Scope) -> Checker {
+        Checker {
+            globals: scope
+                .values_recurse()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            scopes: Vec::new(),
+            diagnostics: Vec::new(),
+            definitions: Vec::new(),
+            references: Vec::new(),
+        }
+    }
+
+    /// Check a script, which ends at `end` in the source.
+    pub fn script(&mut self, script: &Script, end: usize) {
+        self.push_scope(end);
+        for stmt in &script.stmts {
+            self.stmt(stmt);
+        }
+        self.expr(&script.expr);
+        self.pop_scope();
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Let(stmt) = stmt;
+
+        if stmt.args.is_empty() {
+            self.expr(&stmt.value);
+        } else {
+            // type annotations are evaluated where the function is defined
+            for args in &stmt.args {
+                if let Some(FnArg { ty: Some(ty), .. }) = &args.arg {
+                    self.expr(ty);
+                }
+            }
+
+            // all parameters are bound in one scope, like when the function is called
+            self.push_scope(stmt.value.span().end);
+            for args in &stmt.args {
+                if let Some(arg) = &args.arg {
+                    self.define(&arg.name, BindingKind::Parameter, arg.name.span.end);
+                }
+            }
+            self.expr(&stmt.value);
+            self.pop_scope();
+        }
+
+        // the variable is only defined after its value, so functions can't refer to themselves
+        let kind = if stmt.args.is_empty() {
+            BindingKind::Variable
+        } else {
+            BindingKind::Function
+        };
+        self.define(&stmt.name, kind, stmt.span.end);
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::BinOp(lhs, _, rhs) => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Expr::Call(func, args) => {
+                self.check_callable(func);
+                self.expr(func);
+                if let Some(arg) = &args.arg {
+                    self.expr(arg);
+                }
+            }
+            Expr::PathAccess(expr, segment, _) => {
+                self.expr(expr);
+                if let PathSegment::Expr(segment) = segment {
+                    self.expr(segment);
+                }
+            }
+            Expr::NullPropagate(expr) => self.expr(expr),
+            Expr::Object(object) => {
+                for entry in &object.entries {
+                    self.expr(&entry.value);
+                }
+            }
+            Expr::Array(array) => {
+                for item in &array.items {
+                    self.expr(item);
+                }
+            }
+            Expr::Lambda(lambda) => {
+                for arg in &lambda.args {
+                    self.push_scope(lambda.span.end);
+                    self.define(arg, BindingKind::Parameter, arg.span.end);
+                }
+                self.expr(&lambda.expr);
+                for _ in &lambda.args {
+                    self.pop_scope();
+                }
+            }
+            Expr::Block(block) => {
+                self.push_scope(block.span.end);
+                for stmt in &block.stmts {
+                    self.stmt(stmt);
+                }
+                self.expr(&block.expr);
+                self.pop_scope();
+            }
+            Expr::If(if_expr) => {
+                self.expr(&if_expr.cond);
+                self.expr(&if_expr.value);
+                for elif in &if_expr.elifs {
+                    self.expr(&elif.cond);
+                    self.expr(&elif.value);
+                }
+                if let Some(else_) = &if_expr.else_ {
+                    self.expr(&else_.value);
+                }
+            }
+            Expr::Match(match_expr) => self.match_expr(match_expr),
+            Expr::Template(template) => {
+                for part in &template.parts {
+                    if let TemplatePart::Placeholder(placeholder) = part {
+                        self.expr(&placeholder.expr);
+                    }
+                }
+            }
+            Expr::Ident(ident) => self.resolve(ident),
+            Expr::Import(_) | Expr::String(_) | Expr::Number(_) => (),
+        }
+    }
+
+    fn match_expr(&mut self, match_expr: &MatchExpr) {
+        self.expr(&match_expr.value);
+
+        // what the earlier cases without a guard match
+        let mut matches_all = false;
+        let mut matched_types = Vec::new();
+        let mut matched_literals = Vec::new();
+
+        for case in &match_expr.cases {
+            let unreachable = if matches_all {
+                Some("an earlier case matches all values".to_string())
+            } else {
+                match &case.pattern {
+                    Pattern::Binding(BindingPattern { ty: Some(ty), .. }) => {
+                        ValueType::from_name(&ty.name.value)
+                            .filter(|ty| matched_types.contains(ty))
+                            .map(|ty| format!("an earlier case matches all values of type {}", ty))
+                    }
+                    Pattern::Literal(literal) => {
+                        let (ty, value) = literal_value(literal);
+                        if matched_types.contains(&ty) {
+                            Some(format!("an earlier case matches all values of type {}", ty))
+                        } else if matched_literals.contains(&value) {
+                            Some("an earlier case matches the same value".to_string())
+                        } else {
+                            None
+                        }
+                    }
+                    _ => None,
+                }
+            };
+            if let Some(reason) = unreachable {
+                self.warning(
+                    "unreachable-case",
+                    format!("Unreachable case: {}", reason),
+                    case.span,
+                );
+            }
+
+            if case.guard.is_none() {
+                match &case.pattern {
+                    Pattern::Binding(BindingPattern { ty: None, .. }) => matches_all = true,
+                    Pattern::Binding(BindingPattern { ty: Some(ty), .. }) => {
+                        matched_types.extend(ValueType::from_name(&ty.name.value));
+                    }
+                    Pattern::Literal(literal) => matched_literals.push(literal_value(literal).1),
+                    _ => (),
+                }
+            }
+
+            self.push_scope(case.span.end);
+            self.pattern(&case.pattern);
+            if let Some(guard) = &case.guard {
+                self.expr(&guard.cond);
+            }
+            self.expr(&case.value);
+            self.pop_scope();
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Array(array) => {
+                for item in &array.items {
+                    self.pattern(item);
+                }
+                if let Some(RestPattern {
+                    name: Some(name), ..
+                }) = &array.rest
+                {
+                    self.define(name, BindingKind::Variable, name.span.end);
+                }
+            }
+            Pattern::Object(object) => {
+                for entry in &object.entries {
+                    match (&entry.pattern, &entry.key) {
+                        (Some(pattern), _) => self.pattern(pattern),
+                        (None, ObjectKey::Ident(name)) => {
+                            self.define(name, BindingKind::Variable, name.span.end)
+                        }
+                        (None, ObjectKey::String(_)) => (),
+                    }
+                }
+                if let Some(RestPattern {
+                    name: Some(name), ..
+                }) = &object.rest
+                {
+                    self.define(name, BindingKind::Variable, name.span.end);
+                }
+            }
+            Pattern::Literal(_) => (),
+            Pattern::Binding(binding) => {
+                let name = &binding.name;
+                self.define(name, BindingKind::Variable, name.span.end)
+            }
+        }
+    }
+
+    /// Report calls of values that are never functions.
+    fn check_callable(&mut self, func: &Expr) {
+        let ty = match func {
+            Expr::Object(_) => ValueType::Object,
+            Expr::Array(_) => ValueType::Array,
+            Expr::Number(_) => ValueType::Number,
+            Expr::String(_) | Expr::Template(_) => ValueType::String,
+            Expr::Ident(ident) if self.is_global(&ident.value) => match ident.value.as_str() {
+                "true" | "false" => ValueType::Boolean,
+                "null" => ValueType::Null,
+                _ => return,
+            },
+            _ => return,
+        };
+        self.error(
+            "not-callable",
+            format!("Value of type {} is not callable", ty),
+            func.span(),
+        );
+    }
+
+    fn push_scope(&mut self, end: usize) {
+        self.scopes.push((Vec::new(), end));
+    }
+
+    /// Leave the innermost scope, reporting the variables in it that were never used.
+    fn pop_scope(&mut self) {
+        let Some((scope, _)) = self.scopes.pop() else {
+            return;
+        };
+        for index in scope {
+            let definition = &self.definitions[index];
+            if definition.used || definition.name.starts_with('_') {
+                continue;
+            }
+            let (code, message) = match definition.kind {
+                BindingKind::Variable | BindingKind::Function => (
+                    "unused-variable",
+                    format!("Unused variable: {}", definition.name),
+                ),
+                BindingKind::Parameter => (
+                    "unused-parameter",
+                    format!("Unused parameter: {}", definition.name),
+                ),
+            };
+            self.warning(code, message, definition.span);
+        }
+    }
+
+    /// Define a variable in the innermost scope, which can be used from `visible_from` until the
+    /// end of the scope.
+    fn define(&mut self, name: &Ident, kind: BindingKind, visible_from: usize) {
+        if name.value == "_" {
+            return;
+        }
+
+        let exists = self.scopes.last().is_some_and(|(scope, _)| {
+            scope
+                .iter()
+                .any(|&index| self.definitions[index].name == name.value)
+        });
+        if exists {
+            self.error(
+                "duplicate-definition",
+                format!("Variable already exists: {}", name.value),
+                name.span,
+            );
+            return;
+        }
+
+        if self.lookup(&name.value).is_some() {
+            self.warning(
+                "shadowed-variable",
+                format!("{} shadows a variable of an outer scope", name.value),
+                name.span,
+            );
+        } else if self.is_global(&name.value) {
+            self.warning(
+                "shadowed-variable",
+                format!("{} shadows a built-in variable", name.value),
+                name.span,
+            );
+        }
+
+        if let Some((scope, end)) = self.scopes.last_mut() {
+            scope.push(self.definitions.len());
+            self.definitions.push(Definition {
+                name: name.value.clone(),
+                span: name.span,
+                kind,
+                visible: (visible_from, *end),
+                used: false,
+            });
+        }
+    }
+
+    fn resolve(&mut self, ident: &Ident) {
+        let definition = self.lookup(&ident.value);
+        if let Some(index) = definition {
+            self.definitions[index].used = true;
+        } else if !self.globals.contains(&ident.value) {
+            self.error(
+                "undefined-variable",
+                format!("Variable not found: {}", ident.value),
+                ident.span,
+            );
+            return;
+        } else if matches!(ident.value.as_str(), "scope" | "local_scope") {
+            // the variables in scope can be used through the returned object
+            for (scope, _) in &self.scopes {
+                for &index in scope {
+                    self.definitions[index].used = true;
+                }
+            }
+        }
+
+        self.references.push(Reference {
+            name: ident.value.clone(),
+            span: ident.span,
+            definition,
+        });
+    }
+
+    /// Return the index of the definition of a variable in the open scopes.
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes.iter().rev().find_map(|(scope, _)| {
+            scope
+                .iter()
+                .copied()
+                .find(|&index| self.definitions[index].name == name)
+        })
+    }
+
+    fn is_global(&self, name: &str) -> bool {
+        self.globals.contains(name) && self.lookup(name).is_none()
+    }
+
+    fn error(&mut self, code: &'static str, message: String, span: Span) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            code,
+            message,
+            span,
+        });
+    }
+
+    fn warning(&mut self, code: &'static str, message: String, span: Span) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            code,
+            message,
+            span,
+        });
+    }
+}
+
+/// Return the type of a literal pattern and a canonical representation of its value.
+fn literal_value(literal: &LiteralPattern) -> (ValueType, String) {
+    match literal {
+        LiteralPattern::String(string) => (
+            ValueType::String,
+            format!("{:?}", string.decode().unwrap_or_default()),
+        ),
+        LiteralPattern::Number(number) => (
+            ValueType::Number,
+            number.decode().unwrap_or_default().to_string(),
+        ),
+        LiteralPattern::Constant(constant) => match constant.value.as_str() {
+            "null" => (ValueType::Null, constant.value.clone()),
+            _ => (ValueType::Boolean, constant.value.clone()),
+        },
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use crate::ast::{self, Script};
+use crate::check::Checker;
+use crate::error::*;
+use crate::eval::Evaluate;
+use crate::scope::Scope;
+use crate::value::Value;
+use crate::Severity;
+
+/// A script that is parsed once and can be run many times, like a template that is rendered with
+/// different variables.
+///
+/// Cloning a compiled script is cheap, as clones share the parsed script, and a compiled script
+/// can be sent to and shared between threads.
+#[derive(Clone, Debug)]
+pub struct CompiledScript {
+    script: Arc<Script>,
+}
+
+impl CompiledScript {
+    /// Parse a script.
+    pub fn new(source: impl AsRef<str>) -> ScriptResult<CompiledScript> {
+        let script = ast::parse_str(source.as_ref())?;
+        Ok(CompiledScript {
+            script: Arc::new(script),
+        })
+    }
+
+    /// Parse a script and check it like [`check_script`](crate::check_script), resolving its
+    /// variables against the scope it will run in.
+    ///
+    /// Fails with the first error that was found. Variables that are only defined when the script
+    /// runs must be declared in `scope`, with any value.
+    pub fn new_checked(source: impl AsRef<str>, scope: &Scope) -> ScriptResult<CompiledScript> {
+        let source = source.as_ref();
+        let compiled = CompiledScript::new(source)?;
+
+        let mut checker = Checker::new(scope);
+        checker.script(&compiled.script, source.len());
+        match checker
+            .diagnostics
+            .into_iter()
+            .find(|diagnostic| diagnostic.severity == Severity::Error)
+        {
+            Some(diagnostic) => Err(ScriptError::At(
+                diagnostic.span,
+                Box::new(ScriptError::Other(diagnostic.message)),
+            )),
+            None => Ok(compiled),
+        }
+    }
+
+    /// Run the script in `scope`, which holds the variables for this run.
+    pub fn run(&self, scope: Scope) -> ScriptResult<Value> {
+        self.script.evaluate_value(scope)
+    }
+}
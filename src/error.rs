@@ -1,4 +1,6 @@
 use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use crate::ast::Rule;
 use crate::value::ValueType;
@@ -28,6 +30,64 @@ impl From<pest::error::Error<Rule>> for ParseError {
     }
 }
 
+/// A location in the source of a script.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub(crate) fn from_pest(span: pest::Span) -> Span {
+        let (line, column) = span.start_pos().line_col();
+        Span {
+            start: span.start(),
+            end: span.end(),
+            line,
+            column,
+        }
+    }
+}
+
+impl Span {
+    /// Return a span from the start of this span to the end of another span.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end,
+            ..self
+        }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// A function call that was being evaluated when an error occurred.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallFrame {
+    /// The name of the function, or `<lambda>` for anonymous functions.
+    pub function: String,
+    /// A short summary of the argument, like `x = [1, 2]`.
+    pub args: String,
+    /// The location of the call in the source, if it was called from a script.
+    pub span: Option<Span>,
+}
+
+impl Display for CallFrame {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}({})", self.function, self.args)?;
+        if let Some(span) = self.span {
+            write!(fmt, " at {}", span)?;
+        }
+        Ok(())
+    }
+}
+
 pub type ScriptResult<T> = Result<T, ScriptError>;
 
 #[derive(Debug)]
@@ -36,8 +96,36 @@ pub enum ScriptError {
     VariableAlreadyExists(String),
     NotStringConvertible(ValueType),
     NotIntConvertible(ValueType),
+    NotBoolConvertible(ValueType),
     NotCallable(ValueType),
     PropertyNotFound(ValueType, String),
+    NotComparable(ValueType, ValueType),
+    NoMatchingCase(ValueType),
+    InvalidArgumentType {
+        function: String,
+        arg: String,
+        expected: String,
+        actual: ValueType,
+    },
+
+    NotRepresentable {
+        format: String,
+        path: String,
+        reason: String,
+    },
+    /// An error converting between values and Rust types, at a path like `spec.ports[2].port`.
+    Conversion {
+        path: String,
+        reason: String,
+    },
+
+    ImportNotFound(String),
+    ImportCycle(Vec<PathBuf>),
+    FilesystemDisabled(String),
+
+    At(Span, Box<ScriptError>),
+    InFile(PathBuf, Box<ScriptError>),
+    InCall(CallFrame, Box<ScriptError>),
 
     Parse(ParseError),
     Io(std::io::Error),
@@ -57,10 +145,65 @@ impl Display for ScriptError {
             ScriptError::NotIntConvertible(value_type) => {
                 write!(fmt, "Cannot convert {} to integer", value_type)
             }
+            ScriptError::NotBoolConvertible(value_type) => {
+                write!(fmt, "Cannot convert {} to boolean", value_type)
+            }
             ScriptError::NotCallable(value_type) => write!(fmt, "Cannot call {}", value_type),
             ScriptError::PropertyNotFound(value_type, key) => {
                 write!(fmt, "Value {} has no property {:?}", value_type, key)
             }
+            ScriptError::NotComparable(lhs_type, rhs_type) => {
+                write!(fmt, "Cannot compare {} with {}", lhs_type, rhs_type)
+            }
+            ScriptError::NoMatchingCase(value_type) => {
+                write!(fmt, "No case matches value of type {}", value_type)
+            }
+            ScriptError::InvalidArgumentType {
+                function,
+                arg,
+                expected,
+                actual,
+            } => write!(
+                fmt,
+                "Invalid argument {} to function {}: expected {}, found {}",
+                arg, function, expected, actual
+            ),
+            ScriptError::NotRepresentable {
+                format,
+                path,
+                reason,
+            } => {
+                write!(fmt, "Cannot write {}", format)?;
+                if !path.is_empty() {
+                    write!(fmt, " at {}", path)?;
+                }
+                write!(fmt, ": {}", reason)
+            }
+            ScriptError::Conversion { path, reason } => {
+                if !path.is_empty() {
+                    write!(fmt, "{}: ", path)?;
+                }
+                write!(fmt, "{}", reason)
+            }
+
+            ScriptError::ImportNotFound(path) => write!(fmt, "Import not found: {}", path),
+            ScriptError::FilesystemDisabled(path) => {
+                write!(fmt, "Cannot read {}: filesystem access is disabled", path)
+            }
+            ScriptError::ImportCycle(paths) => {
+                write!(fmt, "Import cycle: ")?;
+                for (i, path) in paths.iter().enumerate() {
+                    if i > 0 {
+                        write!(fmt, " -> ")?;
+                    }
+                    write!(fmt, "{}", path.display())?;
+                }
+                Ok(())
+            }
+
+            ScriptError::At(span, err) => write!(fmt, "{} at {}", err, span),
+            ScriptError::InFile(path, err) => write!(fmt, "{}: {}", path.display(), err),
+            ScriptError::InCall(frame, err) => write!(fmt, "{}\n    in {}", err, frame),
 
             ScriptError::Parse(err) => write!(fmt, "{}", err),
             ScriptError::Io(err) => write!(fmt, "{}", err),
@@ -69,8 +212,251 @@ impl Display for ScriptError {
     }
 }
 
+impl ScriptError {
+    /// Attach the location in the source where this error occurred, unless the error already has a
+    /// location.
+    ///
+    /// If the error occurred in a function call without a known call site, the location is used as
+    /// the call site.
+    pub fn at(self, span: Span) -> ScriptError {
+        match self {
+            ScriptError::At(..) => self,
+            ScriptError::InCall(mut frame, err) => {
+                frame.span.get_or_insert(span);
+                ScriptError::InCall(frame, err)
+            }
+            err => ScriptError::At(span, Box::new(err)),
+        }
+    }
+
+    /// Record the function call that was being evaluated when this error occurred.
+    pub fn in_call(self, frame: CallFrame) -> ScriptError {
+        ScriptError::InCall(frame, Box::new(self))
+    }
+
+    /// Prefix the path of a conversion error with the key of an object entry.
+    pub(crate) fn in_key(self, key: &str) -> ScriptError {
+        match self {
+            ScriptError::Conversion { path, reason } => {
+                let path = if path.is_empty() {
+                    key.to_string()
+                } else if path.starts_with('[') {
+                    format!("{}{}", key, path)
+                } else {
+                    format!("{}.{}", key, path)
+                };
+                ScriptError::Conversion { path, reason }
+            }
+            err => err,
+        }
+    }
+
+    /// Prefix the path of a conversion error with the index of an array item.
+    pub(crate) fn in_index(self, index: usize) -> ScriptError {
+        match self {
+            ScriptError::Conversion { path, reason } => {
+                let path = if path.is_empty() || path.starts_with('[') {
+                    format!("[{}]{}", index, path)
+                } else {
+                    format!("[{}].{}", index, path)
+                };
+                ScriptError::Conversion { path, reason }
+            }
+            err => err,
+        }
+    }
+
+    /// Return the underlying error, without the locations where it occurred.
+    pub fn root_cause(&self) -> &ScriptError {
+        match self {
+            ScriptError::At(_, err) | ScriptError::InFile(_, err) | ScriptError::InCall(_, err) => {
+                err.root_cause()
+            }
+            err => err,
+        }
+    }
+
+    /// Return the innermost location where this error occurred in the script that was evaluated,
+    /// which is the location of the `import` expression for errors in imported files.
+    pub fn span(&self) -> Option<Span> {
+        let mut span = None;
+        let mut err = self;
+        loop {
+            match err {
+                ScriptError::At(location, inner) => {
+                    span = Some(*location);
+                    err = inner;
+                }
+                ScriptError::InCall(frame, inner) => {
+                    span = frame.span.or(span);
+                    err = inner;
+                }
+                // only the file of the script itself is skipped
+                ScriptError::InFile(_, inner) if span.is_none() => err = inner,
+                _ => return span,
+            }
+        }
+    }
+
+    /// Return the function calls that were being evaluated when this error occurred, starting
+    /// with the innermost call.
+    pub fn call_stack(&self) -> Vec<&CallFrame> {
+        let mut frames = Vec::new();
+        let mut err = self;
+        loop {
+            match err {
+                ScriptError::At(_, inner) | ScriptError::InFile(_, inner) => err = inner,
+                ScriptError::InCall(frame, inner) => {
+                    frames.push(frame);
+                    err = inner;
+                }
+                _ => break,
+            }
+        }
+        frames.reverse();
+        frames
+    }
+}
+
+impl ScriptError {
+    /// Render this error with a source snippet for every location where it occurred, like pest
+    /// renders parse errors.
+    ///
+    /// The source of errors in files is read from the file, and `source` is used for errors outside
+    /// of any file.
+    pub fn render(&self, source: Option<&str>) -> String {
+        enum Entry<'a> {
+            At(Option<&'a Path>, Option<String>, Span),
+            Call(Option<&'a Path>, Option<String>, &'a CallFrame),
+            Import,
+        }
+
+        let mut path: Option<&Path> = None;
+        let mut source = source.map(str::to_string);
+        let mut entries = Vec::new();
+
+        let mut err = self;
+        loop {
+            match err {
+                ScriptError::At(span, inner) => {
+                    entries.push(Entry::At(path, source.clone(), *span));
+                    err = inner;
+                }
+                ScriptError::InFile(file, inner) => {
+                    if path.is_some() {
+                        entries.push(Entry::Import);
+                    }
+                    path = Some(file);
+                    source = fs::read_to_string(file).ok();
+                    err = inner;
+                }
+                ScriptError::InCall(frame, inner) => {
+                    entries.push(Entry::Call(path, source.clone(), frame));
+                    err = inner;
+                }
+                _ => break,
+            }
+        }
+
+        let mut output = match (err, path) {
+            (ScriptError::Parse(ParseError::Pest(err)), Some(path)) => {
+                format!("{}", err.clone().with_path(&path.display().to_string()))
+            }
+            (ScriptError::Parse(ParseError::Pest(err)), None) => format!("{}", err),
+            (err, _) => format!("{}", err),
+        };
+
+        let mut imported = false;
+        for entry in entries.iter().rev() {
+            match entry {
+                Entry::At(path, source, span) => {
+                    if std::mem::take(&mut imported) {
+                        output.push_str("\nimported from:");
+                    }
+                    output.push('\n');
+                    render_snippet(&mut output, *path, source.as_deref(), *span);
+                }
+                Entry::Call(path, source, frame) => {
+                    output.push_str(&format!("\nin {}({})", frame.function, frame.args));
+                    if let Some(span) = frame.span {
+                        output.push('\n');
+                        render_snippet(&mut output, *path, source.as_deref(), span);
+                    }
+                }
+                Entry::Import => imported = true,
+            }
+        }
+
+        output
+    }
+}
+
+fn render_snippet(output: &mut String, path: Option<&Path>, source: Option<&str>, span: Span) {
+    use std::fmt::Write;
+
+    let line_no = span.line.to_string();
+    let gutter = " ".repeat(line_no.len());
+
+    match path {
+        Some(path) => write!(
+            output,
+            "{}--> {}:{}:{}",
+            gutter,
+            path.display(),
+            span.line,
+            span.column
+        ),
+        None => write!(output, "{}--> {}:{}", gutter, span.line, span.column),
+    }
+    .unwrap();
+
+    let line = source.and_then(|source| source.lines().nth(span.line - 1));
+    if let (Some(source), Some(line)) = (source, line) {
+        let width = source
+            .get(span.start..span.end)
+            .and_then(|text| text.lines().next())
+            .map(|text| text.chars().count())
+            .unwrap_or(0)
+            .max(1);
+
+        write!(
+            output,
+            "\n{} |\n{} | {}\n{} | {}{}",
+            gutter,
+            line_no,
+            line,
+            gutter,
+            " ".repeat(span.column - 1),
+            "^".repeat(width)
+        )
+        .unwrap();
+    }
+}
+
 impl std::error::Error for ScriptError {}
 
+impl serde::ser::Error for ScriptError {
+    fn custom<T: Display>(msg: T) -> ScriptError {
+        ScriptError::Conversion {
+            path: String::new(),
+            reason: msg.to_string(),
+        }
+    }
+}
+
+impl serde::de::Error for ScriptError {
+    fn custom<T: Display>(msg: T) -> ScriptError {
+        ScriptError::Conversion {
+            path: String::new(),
+            reason: msg.to_string(),
+        }
+    }
+
+    fn invalid_type(unexpected: serde::de::Unexpected, expected: &dyn serde::de::Expected) -> Self {
+        serde::de::Error::custom(format_args!("expected {}, found {}", expected, unexpected))
+    }
+}
+
 impl From<ParseError> for ScriptError {
     fn from(err: ParseError) -> ScriptError {
         ScriptError::Parse(err)
@@ -0,0 +1,173 @@
+/// A document for the pretty-printer, which chooses line breaks to fit a maximum width.
+///
+/// Groups are written on a single line if they fit, otherwise their line breaks are written as
+/// newlines. Nested groups are considered separately.
+#[derive(Clone, Debug)]
+pub enum Doc {
+    Text(String),
+    /// A space, or a newline if the enclosing group is broken.
+    Line,
+    /// Nothing, or a newline if the enclosing group is broken.
+    SoftLine,
+    /// Always a newline.
+    HardLine,
+    /// An empty line if the enclosing group is broken, which must be followed by a line break.
+    BlankLine,
+    /// Forces the enclosing groups to break, e.g. after a line comment.
+    BreakParent,
+    /// Only written if the enclosing group is broken.
+    IfBreak(Box<Doc>),
+    Concat(Vec<Doc>),
+    /// Indents the lines of a document by one level.
+    Nest(Box<Doc>),
+    Group(Box<Doc>),
+    /// Writes a document on a single line, regardless of its width.
+    Flat(Box<Doc>),
+}
+
+const INDENT: usize = 2;
+
+#[derive(Copy, Clone, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+pub fn text(text: impl Into<String>) -> Doc {
+    Doc::Text(text.into())
+}
+
+pub fn concat(docs: Vec<Doc>) -> Doc {
+    Doc::Concat(docs)
+}
+
+pub fn nest(doc: Doc) -> Doc {
+    Doc::Nest(Box::new(doc))
+}
+
+pub fn group(doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc))
+}
+
+pub fn if_break(doc: Doc) -> Doc {
+    Doc::IfBreak(Box::new(doc))
+}
+
+pub fn flat(doc: Doc) -> Doc {
+    Doc::Flat(Box::new(doc))
+}
+
+/// Print a document, breaking groups that are wider than `width`.
+pub fn print(doc: &Doc, width: usize) -> String {
+    let mut out = String::new();
+    let mut column = 0;
+    let mut stack = vec![(0, Mode::Break, doc)];
+
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(text) => {
+                out.push_str(text);
+                column = match text.rfind('\n') {
+                    Some(i) => text.len() - i - 1,
+                    None => column + text.chars().count(),
+                };
+            }
+            Doc::Line | Doc::SoftLine if mode == Mode::Flat => {
+                if let Doc::Line = doc {
+                    out.push(' ');
+                    column += 1;
+                }
+            }
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                newline(&mut out);
+                out.push_str(&" ".repeat(indent));
+                column = indent;
+            }
+            Doc::BlankLine => {
+                if mode == Mode::Break {
+                    newline(&mut out);
+                }
+            }
+            Doc::BreakParent => (),
+            Doc::IfBreak(doc) => {
+                if mode == Mode::Break {
+                    stack.push((indent, mode, doc));
+                }
+            }
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Nest(doc) => stack.push((indent + INDENT, mode, doc)),
+            Doc::Group(doc) => {
+                let mode = if mode == Mode::Flat
+                    || fits(
+                        width as isize - column as isize,
+                        (indent, Mode::Flat, doc),
+                        &stack,
+                    ) {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                stack.push((indent, mode, doc));
+            }
+            Doc::Flat(doc) => stack.push((indent, Mode::Flat, doc)),
+        }
+    }
+
+    newline(&mut out);
+    out
+}
+
+/// Start a new line, removing trailing whitespace from the current one.
+fn newline(out: &mut String) {
+    out.truncate(out.trim_end_matches(' ').len());
+    out.push('\n');
+}
+
+/// Return whether a document fits in the remaining width when written flat, together with the
+/// rest of the current line.
+fn fits(mut remaining: isize, next: (usize, Mode, &Doc), rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut stack = vec![next];
+    let mut rest = rest.iter().rev();
+
+    loop {
+        let (indent, mode, doc) = match stack.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some(&next) => next,
+                None => return true,
+            },
+        };
+
+        match doc {
+            Doc::Text(text) => {
+                if text.contains('\n') {
+                    return mode == Mode::Break;
+                }
+                remaining -= text.chars().count() as isize;
+            }
+            Doc::Line | Doc::SoftLine | Doc::HardLine if mode == Mode::Break => return true,
+            Doc::Line => remaining -= 1,
+            Doc::HardLine => return false,
+            Doc::SoftLine | Doc::BlankLine => (),
+            Doc::BreakParent => {
+                if mode == Mode::Flat {
+                    return false;
+                }
+            }
+            Doc::IfBreak(doc) => {
+                if mode == Mode::Break {
+                    stack.push((indent, mode, doc));
+                }
+            }
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Nest(doc) => stack.push((indent + INDENT, mode, doc)),
+            // groups after the current one end the line at their first line break
+            Doc::Group(doc) => stack.push((indent, mode, doc)),
+            Doc::Flat(doc) => stack.push((indent, Mode::Flat, doc)),
+        }
+
+        if remaining < 0 {
+            return false;
+        }
+    }
+}
@@ -0,0 +1,654 @@
+//! Canonical formatting of scripts.
+//!
+//! Scripts are parsed and their syntax tree is written back with consistent indentation, spacing
+//! and line breaks. The grammar discards comments, so they are collected from the source
+//! separately and written before the next statement, entry or item, or at the end of the line
+//! they were on. Block comments on one line can also be written before any expression. Scripts
+//! with comments elsewhere are not formatted, so that comments are never moved.
+
+mod doc;
+
+use crate::ast::*;
+use crate::error::*;
+
+use self::doc::*;
+
+/// Options for formatting scripts.
+#[derive(Copy, Clone, Debug)]
+pub struct FormatOptions {
+    /// The maximum line width, which is exceeded only by expressions that cannot be broken.
+    pub width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions { width: 100 }
+    }
+}
+
+/// Format a script, returning its source in canonical form.
+pub fn format_script(source: &str, options: FormatOptions) -> ScriptResult<String> {
+    let script = parse_str(source)?;
+    let mut formatter = Formatter {
+        source,
+        comments: collect_comments(source),
+        next_comment: 0,
+        error: None,
+    };
+    let doc = formatter.script(&script);
+    match formatter.error {
+        Some(err) => Err(err),
+        None => Ok(print(&doc, options.width)),
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Comment {
+    start: usize,
+    end: usize,
+}
+
+/// Find the comments in a script that has been parsed successfully, skipping strings and the
+/// text of templates.
+fn collect_comments(source: &str) -> Vec<Comment> {
+    enum Context {
+        /// Code, with the number of open braces in a template placeholder.
+        Code(usize),
+        Template,
+    }
+
+    let bytes = source.as_bytes();
+    let mut comments = Vec::new();
+    let mut stack = vec![Context::Code(0)];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+        let in_placeholder = stack.len() > 1;
+        match stack.last_mut().unwrap() {
+            Context::Template => match bytes[i] {
+                b'\\' => i += 1,
+                b'`' => {
+                    stack.pop();
+                }
+                b'$' if next == Some(b'{') => {
+                    stack.push(Context::Code(0));
+                    i += 1;
+                }
+                _ => (),
+            },
+            Context::Code(depth) => match bytes[i] {
+                b'"' => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != b'"' {
+                        if bytes[i] == b'\\' {
+                            i += 1;
+                        }
+                        i += 1;
+                    }
+                }
+                b'`' => stack.push(Context::Template),
+                b'{' => *depth += 1,
+                b'}' if *depth == 0 && in_placeholder => {
+                    stack.pop();
+                }
+                b'}' => *depth = depth.saturating_sub(1),
+                b'/' if next == Some(b'/') => {
+                    let end = source[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                    comments.push(Comment { start: i, end });
+                    i = end;
+                    continue;
+                }
+                b'/' if next == Some(b'*') => {
+                    let end = source[i + 2..]
+                        .find("*/")
+                        .map_or(bytes.len(), |n| i + n + 4);
+                    comments.push(Comment { start: i, end });
+                    i = end;
+                    continue;
+                }
+                _ => (),
+            },
+        }
+        i += 1;
+    }
+
+    comments
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    comments: Vec<Comment>,
+    /// The first comment that has not been written yet. Documents are built in source order, so
+    /// that comments can be written in order.
+    next_comment: usize,
+    /// The error for the first comment that can't be written where it is.
+    error: Option<ScriptError>,
+}
+
+/// An element of a list that is written on its own line when the list is broken.
+struct Element {
+    start: usize,
+    doc: Doc,
+}
+
+impl<'a> Formatter<'a> {
+    fn script(&mut self, script: &Script) -> Doc {
+        let mut elements = self.stmts(&script.stmts, &script.expr);
+        elements.extend(self.leading_comments(self.source.len()));
+        self.lines(elements, Doc::HardLine)
+    }
+
+    /// Build the elements for statements followed by an expression.
+    fn stmts(&mut self, stmts: &[Stmt], expr: &Expr) -> Vec<Element> {
+        let mut elements = Vec::new();
+        for stmt in stmts {
+            let Stmt::Let(stmt) = stmt;
+            elements.extend(self.leading_comments(stmt.span.start));
+            let doc = self.let_stmt(stmt);
+            elements.push(Element {
+                start: stmt.span.start,
+                doc: self.with_trailing_comment(doc, stmt.span.end),
+            });
+        }
+
+        elements.extend(self.leading_comments(expr.span().start));
+        let doc = self.expr(expr);
+        let end = expr.span().end;
+        elements.push(Element {
+            start: expr.span().start,
+            doc: self.with_trailing_comment(doc, end),
+        });
+        elements
+    }
+
+    fn let_stmt(&mut self, stmt: &LetStmt) -> Doc {
+        let mut docs = vec![text("let "), text(&stmt.name.value)];
+        for args in &stmt.args {
+            match &args.arg {
+                Some(FnArg { name, ty: None, .. }) => docs.push(text(format!("({})", name.value))),
+                Some(FnArg {
+                    name, ty: Some(ty), ..
+                }) => {
+                    docs.push(text(format!("({}: ", name.value)));
+                    docs.push(self.expr(ty));
+                    docs.push(text(")"));
+                }
+                None => docs.push(text("()")),
+            }
+        }
+        docs.push(text(" = "));
+        docs.push(self.expr(&stmt.value));
+        docs.push(text(";"));
+        concat(docs)
+    }
+
+    /// Format an expression, with the block comments before it on the same line.
+    fn expr(&mut self, expr: &Expr) -> Doc {
+        let span = expr.span();
+        let mut docs = Vec::new();
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.start >= span.start {
+                break;
+            }
+            let comment = comment.clone();
+            let source = &self.source[comment.start..comment.end];
+            if !source.starts_with("/*") || source.contains('\n') {
+                self.misplaced_comment(&comment);
+            }
+            self.check_before(&comment, span.start);
+            docs.push(self.comment(&comment));
+            docs.push(text(" "));
+            self.next_comment += 1;
+        }
+        docs.push(self.expr_inner(expr));
+
+        // comments inside the expression must have been written by now
+        if let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.start < span.end {
+                let comment = comment.clone();
+                self.misplaced_comment(&comment);
+            }
+        }
+        concat(docs)
+    }
+
+    fn expr_inner(&mut self, expr: &Expr) -> Doc {
+        match expr {
+            Expr::BinOp(..) => self.binop_chain(expr),
+            Expr::Call(func, args) => {
+                let func = self.expr(func);
+                let arg = match &args.arg {
+                    Some(arg) => self.expr(arg),
+                    None => text(""),
+                };
+                concat(vec![func, text("("), arg, text(")")])
+            }
+            Expr::PathAccess(expr, segment, null_propagation) => {
+                let mut docs = vec![self.expr(expr)];
+                match segment {
+                    PathSegment::Ident(ident) => docs.push(text(format!(".{}", ident.value))),
+                    PathSegment::Expr(expr) => {
+                        docs.push(text("["));
+                        docs.push(self.expr(expr));
+                        docs.push(text("]"));
+                    }
+                }
+                if null_propagation.is_some() {
+                    docs.push(text("?"));
+                }
+                concat(docs)
+            }
+            Expr::NullPropagate(expr) => concat(vec![self.expr(expr), text("?")]),
+            Expr::Object(object) => {
+                let mut elements = Vec::new();
+                for (i, entry) in object.entries.iter().enumerate() {
+                    elements.extend(self.leading_comments(entry.span.start));
+                    let doc = concat(vec![
+                        object_key(&entry.key),
+                        text(": "),
+                        self.expr(&entry.value),
+                    ]);
+                    let is_last = i == object.entries.len() - 1;
+                    elements.push(self.list_item(doc, entry.span, is_last));
+                }
+                self.list("{", elements, "}", object.span, true)
+            }
+            Expr::Array(array) => {
+                let mut elements = Vec::new();
+                for (i, item) in array.items.iter().enumerate() {
+                    elements.extend(self.leading_comments(item.span().start));
+                    let doc = self.expr(item);
+                    let is_last = i == array.items.len() - 1;
+                    elements.push(self.list_item(doc, item.span(), is_last));
+                }
+                self.list("[", elements, "]", array.span, false)
+            }
+            Expr::Lambda(lambda) => {
+                let mut docs = vec![text("(")];
+                for arg in &lambda.args {
+                    docs.push(text(format!("{} => ", arg.value)));
+                }
+                docs.push(self.expr(&lambda.expr));
+                docs.push(text(")"));
+                concat(docs)
+            }
+            Expr::Block(block) => self.block(block),
+            Expr::If(if_expr) => self.if_expr(if_expr),
+            Expr::Match(match_expr) => self.match_expr(match_expr),
+            Expr::Import(import) => {
+                let keyword = match &import.keyword {
+                    ImportKeyword::Json(kw) => &kw.value,
+                    ImportKeyword::Str(kw) => &kw.value,
+                    ImportKeyword::Script(kw) => &kw.value,
+                };
+                text(format!("{} \"{}\"", keyword, import.path.value))
+            }
+            Expr::String(string) => text(format!("\"{}\"", string.value)),
+            Expr::Template(template) => {
+                let mut docs = vec![text("`")];
+                for part in &template.parts {
+                    match part {
+                        TemplatePart::Text(text_part) => docs.push(text(&text_part.value)),
+                        TemplatePart::Placeholder(placeholder) => {
+                            docs.push(text("${"));
+                            docs.push(flat(self.expr(&placeholder.expr)));
+                            docs.push(text("}"));
+                        }
+                    }
+                }
+                docs.push(text("`"));
+                concat(docs)
+            }
+            Expr::Number(number) => text(&number.value),
+            Expr::Ident(ident) => text(&ident.value),
+        }
+    }
+
+    /// Format a chain of binary operators, which is broken before every operator if it does not
+    /// fit on one line.
+    fn binop_chain(&mut self, expr: &Expr) -> Doc {
+        // operators are left-associative, so the chain is the left spine of the tree
+        let mut operands = Vec::new();
+        let mut first = expr;
+        while let Expr::BinOp(lhs, op, rhs) = first {
+            operands.push((op, rhs));
+            first = lhs;
+        }
+
+        // comments at the end of the line of an operand stay there, except after the last one,
+        // where they belong to the enclosing statement or item
+        let doc = self.expr(first);
+        let first = self.with_trailing_comment(doc, first.span().end);
+        let mut rest = Vec::new();
+        for (i, (op, operand)) in operands.iter().rev().enumerate() {
+            for comment in self.leading_comments(operand.span().start) {
+                rest.push(Doc::Line);
+                rest.push(comment.doc);
+            }
+            rest.push(Doc::Line);
+            rest.push(text(format!("{} ", op_str(op))));
+            let doc = self.expr(operand);
+            if i < operands.len() - 1 {
+                rest.push(self.with_trailing_comment(doc, operand.span().end));
+            } else {
+                rest.push(doc);
+            }
+        }
+        group(concat(vec![first, nest(concat(rest))]))
+    }
+
+    fn block(&mut self, block: &Block) -> Doc {
+        if block.stmts.is_empty() {
+            let elements = self.stmts(&block.stmts, &block.expr);
+            let mut docs = vec![text("(")];
+            let mut inner = Vec::new();
+            for element in elements {
+                inner.push(Doc::SoftLine);
+                inner.push(element.doc);
+            }
+            docs.push(nest(concat(inner)));
+            docs.extend(self.closing_comments(block.span.end - 1));
+            docs.push(Doc::SoftLine);
+            docs.push(text(")"));
+            return group(concat(docs));
+        }
+
+        let mut elements = self.stmts(&block.stmts, &block.expr);
+        elements.extend(self.leading_comments(block.span.end - 1));
+        concat(vec![
+            text("("),
+            nest(concat(vec![
+                Doc::HardLine,
+                self.lines(elements, Doc::HardLine),
+            ])),
+            Doc::HardLine,
+            text(")"),
+        ])
+    }
+
+    fn if_expr(&mut self, if_expr: &IfExpr) -> Doc {
+        let mut docs = vec![text("if "), self.expr(&if_expr.cond), text(" then")];
+        docs.push(self.branch(&if_expr.value));
+        for elif in &if_expr.elifs {
+            docs.push(Doc::Line);
+            docs.push(text("elif "));
+            docs.push(self.expr(&elif.cond));
+            docs.push(text(" then"));
+            docs.push(self.branch(&elif.value));
+        }
+        if let Some(else_) = &if_expr.else_ {
+            docs.push(Doc::Line);
+            docs.push(text("else"));
+            docs.push(self.branch(&else_.value));
+        }
+        docs.extend(self.closing_comments(if_expr.end.span.start));
+        docs.push(Doc::Line);
+        docs.push(text("end"));
+        group(concat(docs))
+    }
+
+    /// Format the value of a branch, indented on its own line if the expression is broken.
+    fn branch(&mut self, value: &Expr) -> Doc {
+        let mut docs = Vec::new();
+        for comment in self.leading_comments(value.span().start) {
+            docs.push(Doc::Line);
+            docs.push(comment.doc);
+        }
+        docs.push(Doc::Line);
+        docs.push(self.expr(value));
+        nest(concat(docs))
+    }
+
+    fn match_expr(&mut self, match_expr: &MatchExpr) -> Doc {
+        let mut elements = Vec::new();
+        let value = self.expr(&match_expr.value);
+        elements.push(Element {
+            start: match_expr.span.start,
+            doc: concat(vec![text("match "), value]),
+        });
+
+        for case in &match_expr.cases {
+            elements.extend(self.leading_comments(case.span.start));
+            let mut docs = vec![text("case "), pattern(&case.pattern)];
+            if let Some(guard) = &case.guard {
+                docs.push(text(" if "));
+                docs.push(self.expr(&guard.cond));
+            }
+            docs.push(text(" then "));
+            docs.push(self.expr(&case.value));
+            let doc = self.with_trailing_comment(concat(docs), case.span.end);
+            elements.push(Element {
+                start: case.span.start,
+                doc,
+            });
+        }
+
+        elements.extend(self.leading_comments(match_expr.end.span.start));
+        elements.push(Element {
+            start: match_expr.end.span.start,
+            doc: text("end"),
+        });
+        self.lines(elements, Doc::HardLine)
+    }
+
+    /// Build an element for an item of an object or array, followed by a comma and a comment on
+    /// the same line.
+    fn list_item(&mut self, doc: Doc, span: Span, is_last: bool) -> Element {
+        let comma = if is_last {
+            if_break(text(","))
+        } else {
+            text(",")
+        };
+        Element {
+            start: span.start,
+            doc: self.with_trailing_comment(concat(vec![doc, comma]), span.end),
+        }
+    }
+
+    /// Format the elements of an object or array between brackets, on one line if they fit and
+    /// otherwise with every element on its own line.
+    fn list(
+        &mut self,
+        open: &str,
+        mut elements: Vec<Element>,
+        close: &str,
+        span: Span,
+        spaced: bool,
+    ) -> Doc {
+        elements.extend(self.leading_comments(span.end - 1));
+        if elements.is_empty() {
+            return text(format!("{}{}", open, close));
+        }
+
+        let line = if spaced { Doc::Line } else { Doc::SoftLine };
+        group(concat(vec![
+            text(open),
+            nest(concat(vec![line.clone(), self.lines(elements, Doc::Line)])),
+            line,
+            text(close),
+        ]))
+    }
+
+    /// Join elements with line breaks, keeping single empty lines between them.
+    fn lines(&self, elements: Vec<Element>, line: Doc) -> Doc {
+        let mut docs = Vec::new();
+        for (i, element) in elements.into_iter().enumerate() {
+            if i > 0 {
+                if self.is_blank_line_before(element.start) {
+                    docs.push(Doc::BlankLine);
+                }
+                docs.push(line.clone());
+            }
+            docs.push(element.doc);
+        }
+        concat(docs)
+    }
+
+    /// Return whether there is an empty line in the whitespace before a position.
+    fn is_blank_line_before(&self, pos: usize) -> bool {
+        let whitespace = &self.source[..pos];
+        let whitespace = &whitespace[whitespace.trim_end().len()..];
+        whitespace.matches('\n').count() > 1
+    }
+
+    /// Take the comments before a position that have not been written yet.
+    fn leading_comments(&mut self, pos: usize) -> Vec<Element> {
+        let mut elements = Vec::new();
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.start >= pos {
+                break;
+            }
+            let comment = comment.clone();
+            self.check_before(&comment, pos);
+            elements.push(Element {
+                start: comment.start,
+                doc: self.comment(&comment),
+            });
+            self.next_comment += 1;
+        }
+        elements
+    }
+
+    /// Check that a comment can be written before the code at `pos`, because there are only
+    /// separators, operators and other comments between them.
+    fn check_before(&mut self, comment: &Comment, pos: usize) {
+        let mut gap = self.source[comment.end..pos].to_string();
+        for other in &self.comments[self.next_comment + 1..] {
+            if other.start >= pos {
+                break;
+            }
+            let len = other.end - other.start;
+            let start = other.start - comment.end;
+            gap.replace_range(start..start + len, &" ".repeat(len));
+        }
+        let is_before = gap
+            .chars()
+            .all(|c| c.is_whitespace() || ",;|=!<>+-*/".contains(c));
+        if !is_before {
+            self.misplaced_comment(comment);
+        }
+    }
+
+    fn misplaced_comment(&mut self, comment: &Comment) {
+        if self.error.is_some() {
+            return;
+        }
+        let span = pest::Span::new(self.source, comment.start, comment.end).unwrap();
+        self.error = Some(
+            script_error!("Cannot format a comment in this position").at(Span::from_pest(span)),
+        );
+    }
+
+    /// Take the comments before the closing keyword or bracket at `end`, as a document that starts
+    /// each comment on a new line.
+    fn closing_comments(&mut self, end: usize) -> Vec<Doc> {
+        let mut docs = Vec::new();
+        for comment in self.leading_comments(end) {
+            docs.push(nest(concat(vec![Doc::Line, comment.doc])));
+        }
+        docs
+    }
+
+    /// Append the comment that follows a node on the same line, if there is one.
+    fn with_trailing_comment(&mut self, doc: Doc, end: usize) -> Doc {
+        let Some(comment) = self.comments.get(self.next_comment) else {
+            return doc;
+        };
+        let is_trailing = comment.start >= end
+            && self.source[end..comment.start]
+                .chars()
+                .all(|c| matches!(c, ' ' | '\t' | ',' | ';'));
+        if !is_trailing {
+            return doc;
+        }
+
+        let comment = comment.clone();
+        self.next_comment += 1;
+        concat(vec![doc, text(" "), self.comment(&comment)])
+    }
+
+    fn comment(&self, comment: &Comment) -> Doc {
+        let text = text(self.source[comment.start..comment.end].trim_end());
+        if self.source[comment.start..].starts_with("//") {
+            // nothing else can follow a line comment on the same line
+            concat(vec![text, Doc::BreakParent])
+        } else {
+            text
+        }
+    }
+}
+
+fn object_key(key: &ObjectKey) -> Doc {
+    match key {
+        ObjectKey::String(string) => text(format!("\"{}\"", string.value)),
+        ObjectKey::Ident(ident) => text(&ident.value),
+    }
+}
+
+/// Format a pattern, which is always written on one line.
+fn pattern(pattern: &Pattern) -> Doc {
+    text(pattern_str(pattern))
+}
+
+fn pattern_str(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Array(array) => {
+            let mut items: Vec<_> = array.items.iter().map(pattern_str).collect();
+            items.extend(array.rest.as_ref().map(rest_str));
+            format!("[{}]", items.join(", "))
+        }
+        Pattern::Object(object) => {
+            let mut entries: Vec<_> = object
+                .entries
+                .iter()
+                .map(|entry| {
+                    let key = match &entry.key {
+                        ObjectKey::String(string) => format!("\"{}\"", string.value),
+                        ObjectKey::Ident(ident) => ident.value.clone(),
+                    };
+                    match &entry.pattern {
+                        Some(pattern) => format!("{}: {}", key, pattern_str(pattern)),
+                        None => key,
+                    }
+                })
+                .collect();
+            entries.extend(object.rest.as_ref().map(rest_str));
+            if entries.is_empty() {
+                "{}".to_string()
+            } else {
+                format!("{{ {} }}", entries.join(", "))
+            }
+        }
+        Pattern::Literal(LiteralPattern::String(string)) => format!("\"{}\"", string.value),
+        Pattern::Literal(LiteralPattern::Number(number)) => number.value.clone(),
+        Pattern::Literal(LiteralPattern::Constant(constant)) => constant.value.clone(),
+        Pattern::Binding(binding) => match &binding.ty {
+            Some(ty) => format!("{}: {}", binding.name.value, ty.name.value),
+            None => binding.name.value.clone(),
+        },
+    }
+}
+
+fn rest_str(rest: &RestPattern) -> String {
+    match &rest.name {
+        Some(name) => format!("...{}", name.value),
+        None => "...".to_string(),
+    }
+}
+
+fn op_str(op: &Op) -> &'static str {
+    match op {
+        Op::Pipe => "|",
+        Op::Eq => "==",
+        Op::Ne => "!=",
+        Op::Le => "<=",
+        Op::Ge => ">=",
+        Op::Lt => "<",
+        Op::Gt => ">",
+        Op::Add => "+",
+        Op::Sub => "-",
+        Op::Mul => "*",
+        Op::Div => "/",
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::error::*;
+use crate::scope::Scope;
+use crate::value::{Function, Value};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImportKind {
+    Script,
+    Json,
+    String,
+    Yaml,
+    Toml,
+    Csv,
+}
+
+/// Loads imported files, relative to the importing file or to a list of library search paths.
+///
+/// Every file is only loaded once per importer, and later imports of the same file return the
+/// cached value. The cache is shared by clones of the importer, including between threads.
+#[derive(Clone, Debug)]
+pub struct Importer {
+    search_paths: Vec<PathBuf>,
+    filesystem_access: bool,
+    cache: Arc<Mutex<HashMap<(PathBuf, ImportKind), Value>>>,
+    /// The scripts being run by the evaluation that uses this importer, to detect import cycles.
+    stack: Vec<PathBuf>,
+}
+
+impl Default for Importer {
+    fn default() -> Importer {
+        Importer {
+            search_paths: Vec::new(),
+            filesystem_access: true,
+            cache: Default::default(),
+            stack: Vec::new(),
+        }
+    }
+}
+
+impl Importer {
+    pub fn new() -> Importer {
+        Importer::default()
+    }
+
+    pub fn with_search_path(mut self, path: impl Into<PathBuf>) -> Importer {
+        self.search_paths.push(path.into());
+        self
+    }
+
+    pub fn search_paths(&self) -> &[PathBuf] {
+        &self.search_paths
+    }
+
+    /// Allow or disallow scripts to read files, with imports or functions like `read_yaml`. This is
+    /// allowed by default.
+    pub fn with_filesystem_access(mut self, allow: bool) -> Importer {
+        self.filesystem_access = allow;
+        self
+    }
+
+    pub fn filesystem_access(&self) -> bool {
+        self.filesystem_access
+    }
+
+    /// Run a script file, with imports resolved relative to the file.
+    pub fn run_file(&self, path: impl AsRef<Path>, scope: Scope) -> ScriptResult<Value> {
+        let path = path.as_ref();
+        let canonical_path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+        if let Some(index) = self.stack.iter().position(|item| item == &canonical_path) {
+            let mut cycle = self.stack[index..].to_vec();
+            cycle.push(canonical_path);
+            return Err(ScriptError::ImportCycle(cycle));
+        }
+
+        // imports by the file continue its stack, even when they run in other threads
+        let mut importer = self.clone();
+        importer.stack.push(canonical_path);
+
+        importer
+            .run_file_inner(path, scope)
+            .map_err(|err| ScriptError::InFile(path.to_path_buf(), Box::new(err)))
+    }
+
+    fn run_file_inner(&self, path: &Path, scope: Scope) -> ScriptResult<Value> {
+        let source = fs::read_to_string(path)?;
+        let scope = scope.with_importer(self, Some(path));
+        crate::run_script_with(source, scope)
+    }
+
+    /// Import a file, relative to the directory of the importing file (or the current directory).
+    pub fn import(&self, kind: ImportKind, path: &str, from: Option<&Path>) -> ScriptResult<Value> {
+        if !self.filesystem_access {
+            return Err(ScriptError::FilesystemDisabled(path.to_string()));
+        }
+
+        let path = self.resolve(path, from)?;
+        let key = (path.clone(), kind);
+
+        if let Some(value) = self.cache.lock().unwrap().get(&key) {
+            return Ok(value.clone());
+        }
+
+        let value = match kind {
+            ImportKind::Script => self.run_file(&path, Scope::new_default())?,
+
+            ImportKind::Json => {
+                let source = fs::read_to_string(&path)?;
+                let value = serde_json::from_str(&source).map_err(|err| {
+                    ScriptError::InFile(path.clone(), Box::new(script_error!("{}", err)))
+                })?;
+                Value::from_json(value)
+            }
+
+            ImportKind::String => Value::String(fs::read_to_string(&path)?),
+
+            ImportKind::Yaml => {
+                let source = fs::read_to_string(&path)?;
+                let value = serde_yaml::from_str(&source).map_err(|err| {
+                    ScriptError::InFile(path.clone(), Box::new(script_error!("{}", err)))
+                })?;
+                Value::from_json(value)
+            }
+
+            ImportKind::Toml => {
+                let source = fs::read_to_string(&path)?;
+                let value = source.parse().map_err(|err| {
+                    ScriptError::InFile(path.clone(), Box::new(script_error!("{}", err)))
+                })?;
+                from_toml(value)
+            }
+
+            ImportKind::Csv => read_csv(&path).map_err(|err| {
+                ScriptError::InFile(path.clone(), Box::new(script_error!("{}", err)))
+            })?,
+        };
+
+        self.cache.lock().unwrap().insert(key, value.clone());
+        Ok(value)
+    }
+
+    fn resolve(&self, path: &str, from: Option<&Path>) -> ScriptResult<PathBuf> {
+        let base_dir = from
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+
+        std::iter::once(&base_dir)
+            .chain(&self.search_paths)
+            .map(|dir| dir.join(path))
+            .find(|candidate| candidate.is_file())
+            .map(|candidate| fs::canonicalize(&candidate).unwrap_or(candidate))
+            .ok_or_else(|| ScriptError::ImportNotFound(path.to_string()))
+    }
+
+    pub(crate) fn import_function(&self, kind: ImportKind, from: Option<&Path>) -> Function {
+        let importer = self.clone();
+        let from = from.map(Path::to_path_buf);
+        Function::new(move |_, path| {
+            let path = path.to_string()?;
+            importer.import(kind, &path, from.as_deref())
+        })
+    }
+}
+
+fn from_toml(value: toml::Value) -> Value {
+    match value {
+        toml::Value::Table(table) => Value::new_object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, from_toml(value))),
+        ),
+        toml::Value::Array(array) => Value::new_array(array.into_iter().map(from_toml)),
+        toml::Value::Integer(number) => Value::Number(number as f64),
+        toml::Value::Float(number) => Value::Number(number),
+        toml::Value::String(string) => Value::String(string),
+        toml::Value::Boolean(b) => Value::Boolean(b),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+    }
+}
+
+/// Read a CSV file with a header row as an array of objects, keyed by the header.
+fn read_csv(path: &Path) -> csv::Result<Value> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let headers = reader.headers()?.clone();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        rows.push(Value::new_object(headers.iter().zip(record.iter()).map(
+            |(key, value)| (key.to_string(), Value::String(value.to_string())),
+        )));
+    }
+    Ok(Value::Array(rows))
+}
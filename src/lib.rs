@@ -4,28 +4,53 @@ mod macros;
 #[macro_use]
 pub mod error;
 
+mod analysis;
 mod ast;
+mod check;
+mod compiled;
 mod eval;
+mod fmt;
+mod import;
 mod scope;
 mod value;
 
-use crate::eval::Evaluate;
+use std::path::Path;
 
+pub use crate::analysis::{Analysis, Completion, Symbol, VariableKind};
+pub use crate::check::{check_script, Diagnostic, Severity};
+pub use crate::compiled::CompiledScript;
 pub use crate::error::*;
+pub use crate::fmt::{format_script, FormatOptions};
+pub use crate::import::{ImportKind, Importer};
 pub use crate::scope::Scope;
-pub use crate::value::Value;
+pub use crate::value::{
+    from_value, to_value, FromValue, Function, NativeFn, NativeResult, Value, ValueType,
+    YamlOptions,
+};
 
 pub fn run_script(source: impl AsRef<str>) -> ScriptResult<Value> {
     let scope = Scope::new_default();
     run_script_with(source, scope)
 }
 
-pub fn run_script_with(source: impl AsRef<str>, scope: Scope) -> ScriptResult<Value> {
-    // parse script
-    let script = ast::parse_str(source.as_ref())?;
+pub fn run_file(path: impl AsRef<Path>) -> ScriptResult<Value> {
+    Importer::new().run_file(path, Scope::new_default())
+}
 
-    // evaluate script
-    let value = script.evaluate_value(scope)?;
+/// Parse and run a script in `scope`. Scripts that run many times should be parsed once with
+/// [`CompiledScript`].
+pub fn run_script_with(source: impl AsRef<str>, scope: Scope) -> ScriptResult<Value> {
+    CompiledScript::new(source)?.run(scope)
+}
 
-    Ok(value)
+/// Evaluate input to a REPL, which is zero or more statements optionally followed by an expression.
+///
+/// Returns the scope extended with the variables defined by the statements, and the value of the
+/// expression if there is one.
+pub fn run_repl_input(
+    source: impl AsRef<str>,
+    scope: Scope,
+) -> ScriptResult<(Scope, Option<Value>)> {
+    let input = ast::parse_repl_input(source.as_ref())?;
+    input.evaluate(scope)
 }
@@ -2,10 +2,12 @@ mod stdlib;
 
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::path::Path;
 use std::sync::Arc;
 
 use crate::error::*;
-use crate::value::{Function, Value};
+use crate::import::{ImportKind, Importer};
+use crate::value::{Function, NativeFn, Value, ValueType};
 
 #[derive(Clone, Debug)]
 pub struct Scope {
@@ -26,6 +28,12 @@ impl Scope {
             .set_nofail("true", Value::Boolean(true))
             .set_nofail("false", Value::Boolean(false))
             .set_nofail("null", Value::Null)
+            .set_nofail("object", Function::Type(ValueType::Object))
+            .set_nofail("array", Function::Type(ValueType::Array))
+            .set_nofail("number", Function::Type(ValueType::Number))
+            .set_nofail("string", Function::Type(ValueType::String))
+            .set_nofail("boolean", Function::Type(ValueType::Boolean))
+            .set_nofail("function", Function::Type(ValueType::Function))
             .set_nofail("scope", Function::new(stdlib::scope))
             .set_nofail("local_scope", Function::new(stdlib::local_scope))
             .set_nofail("/pipe", Function::new2(stdlib::pipe))
@@ -39,9 +47,75 @@ impl Scope {
             .set_nofail("/le", Function::new2(stdlib::le))
             .set_nofail("/gt", Function::new2(stdlib::gt))
             .set_nofail("/lt", Function::new2(stdlib::lt))
+            .with_importer(&Importer::new(), None)
+            .set_nofail("deep_merge", Function::new2(stdlib::deep_merge))
+            .set_nofail("map", Function::new2(stdlib::map))
+            .set_nofail("filter", Function::new2(stdlib::filter))
+            .set_nofail("fold", Function::new3(stdlib::fold))
+            .set_nofail("reduce", Function::new2(stdlib::reduce))
+            .set_nofail("flat_map", Function::new2(stdlib::flat_map))
+            .set_nofail("keys", Function::new(stdlib::keys))
+            .set_nofail("values", Function::new(stdlib::values))
+            .set_nofail("entries", Function::new(stdlib::entries))
+            .set_nofail("from_entries", Function::new(stdlib::from_entries))
+            .set_nofail("len", Function::new(stdlib::len))
+            .set_nofail("sort", Function::new(stdlib::sort))
+            .set_nofail("sort_by", Function::new2(stdlib::sort_by))
+            .set_nofail("unique", Function::new(stdlib::unique))
+            .set_nofail("group_by", Function::new2(stdlib::group_by))
+            .set_nofail("zip", Function::new2(stdlib::zip))
+            .set_nofail("range", Function::new2(stdlib::range))
+            .set_nofail("any", Function::new2(stdlib::any))
+            .set_nofail("all", Function::new2(stdlib::all))
+            .set_nofail("find", Function::new2(stdlib::find))
+            .set_nofail("index_of", Function::new2(stdlib::index_of))
+            .set_nofail("reverse", Function::new(stdlib::reverse))
+            .set_nofail("take", Function::new2(stdlib::take))
+            .set_nofail("drop", Function::new2(stdlib::drop))
+            .set_nofail("split", Function::new2(stdlib::split))
+            .set_nofail("join", Function::new2(stdlib::join))
+            .set_nofail("trim", Function::new(stdlib::trim))
+            .set_nofail("upper", Function::new(stdlib::upper))
+            .set_nofail("lower", Function::new(stdlib::lower))
+            .set_nofail("replace", Function::new3(stdlib::replace))
+            .set_nofail("starts_with", Function::new2(stdlib::starts_with))
+            .set_nofail("ends_with", Function::new2(stdlib::ends_with))
+            .set_nofail("contains", Function::new2(stdlib::contains))
+            .set_nofail("substring", Function::new3(stdlib::substring))
+            .set_nofail("pad_left", Function::new3(stdlib::pad_left))
+            .set_nofail("pad_right", Function::new3(stdlib::pad_right))
+            .set_nofail("repeat", Function::new2(stdlib::repeat))
+            .set_nofail("chars", Function::new(stdlib::chars))
+            .set_nofail("format", Function::new2(stdlib::format))
             .inherit()
     }
 
+    /// Set the importer used by `import` expressions and functions like `read_yaml`, resolving
+    /// paths relative to `file`.
+    pub fn with_importer(self, importer: &Importer, file: Option<&Path>) -> Scope {
+        self.set_nofail(
+            "/import",
+            importer.import_function(ImportKind::Script, file),
+        )
+        .set_nofail(
+            "/import_json",
+            importer.import_function(ImportKind::Json, file),
+        )
+        .set_nofail(
+            "/import_str",
+            importer.import_function(ImportKind::String, file),
+        )
+        .set_nofail(
+            "read_yaml",
+            importer.import_function(ImportKind::Yaml, file),
+        )
+        .set_nofail(
+            "read_toml",
+            importer.import_function(ImportKind::Toml, file),
+        )
+        .set_nofail("read_csv", importer.import_function(ImportKind::Csv, file))
+    }
+
     pub fn inherit(&self) -> Scope {
         Scope {
             parent: Some(Arc::new(self.clone())),
@@ -73,6 +147,12 @@ impl Scope {
         self
     }
 
+    /// Define a function implemented in Rust, with arguments converted to the types of its
+    /// parameters. See [`Function::from_fn`].
+    pub fn register<Args, F: NativeFn<Args>>(self, name: &str, params: &[&str], f: F) -> Scope {
+        self.set_nofail(name, Function::from_fn(name, params, f))
+    }
+
     pub fn extend(self, other: Scope) -> Scope {
         let mut scope = self;
         for (name, value) in other.values {
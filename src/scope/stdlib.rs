@@ -1,14 +1,23 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
 use crate::error::*;
 use crate::scope::Scope;
 use crate::value::Value;
 
 pub fn scope(call_scope: Scope, _: Value) -> ScriptResult<Value> {
-    Ok(Value::Object(
-        call_scope
-            .values_recurse()
-            .map(|(key, value)| (key.to_string(), value.clone()))
-            .collect(),
-    ))
+    // inner variables shadow outer ones, so the first binding of a name wins. Operators and
+    // importers are left out, as scripts can't refer to them by name.
+    let mut values = BTreeMap::new();
+    for (key, value) in call_scope.values_recurse() {
+        if key.starts_with('/') {
+            continue;
+        }
+        values
+            .entry(key.to_string())
+            .or_insert_with(|| value.clone());
+    }
+    Ok(Value::Object(values))
 }
 
 pub fn local_scope(call_scope: Scope, _: Value) -> ScriptResult<Value> {
@@ -27,6 +36,13 @@ pub fn pipe(call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
 pub fn add(_call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
     Ok(match (&lhs, &rhs) {
         (Value::Number(x), Value::Number(y)) => Value::Number(x + y),
+        (Value::String(x), Value::String(y)) => Value::String(format!("{}{}", x, y)),
+        (Value::Array(x), Value::Array(y)) => Value::new_array(x.iter().chain(y).cloned()),
+        (Value::Object(x), Value::Object(y)) => {
+            let mut entries = x.clone();
+            entries.extend(y.iter().map(|(key, value)| (key.clone(), value.clone())));
+            Value::Object(entries)
+        }
 
         _ => {
             return Err(script_error!(
@@ -38,6 +54,33 @@ pub fn add(_call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
     })
 }
 
+pub fn deep_merge(_call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
+    fn merge(lhs: Value, rhs: Value) -> Value {
+        match (lhs, rhs) {
+            (Value::Object(mut lhs), Value::Object(rhs)) => {
+                for (key, value) in rhs {
+                    let value = match lhs.remove(&key) {
+                        Some(old_value) => merge(old_value, value),
+                        None => value,
+                    };
+                    lhs.insert(key, value);
+                }
+                Value::Object(lhs)
+            }
+            (_, rhs) => rhs,
+        }
+    }
+
+    match (&lhs, &rhs) {
+        (Value::Object(..), Value::Object(..)) => Ok(merge(lhs, rhs)),
+        _ => Err(script_error!(
+            "cannot merge values of types: {:?}, {:?}",
+            lhs.value_type(),
+            rhs.value_type()
+        )),
+    }
+}
+
 pub fn sub(_call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
     Ok(match (&lhs, &rhs) {
         (Value::Number(x), Value::Number(y)) => Value::Number(x - y), // TODO: checked
@@ -81,49 +124,627 @@ pub fn div(_call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
 }
 
 pub fn eq(_call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
-    Err(script_error!(
-        "comparison not implement for types: {:?}, {:?}",
-        lhs.value_type(),
-        rhs.value_type()
-    ))
+    Ok(Value::Boolean(lhs.equals(&rhs)?))
 }
 
 pub fn ne(_call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
-    Err(script_error!(
-        "comparison not implement for types: {:?}, {:?}",
-        lhs.value_type(),
-        rhs.value_type()
-    ))
+    Ok(Value::Boolean(!lhs.equals(&rhs)?))
 }
 
 pub fn ge(_call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
-    Err(script_error!(
-        "comparison not implement for types: {:?}, {:?}",
-        lhs.value_type(),
-        rhs.value_type()
-    ))
+    Ok(Value::Boolean(lhs.compare(&rhs)? != Ordering::Less))
 }
 
 pub fn le(_call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
-    Err(script_error!(
-        "comparison not implement for types: {:?}, {:?}",
-        lhs.value_type(),
-        rhs.value_type()
-    ))
+    Ok(Value::Boolean(lhs.compare(&rhs)? != Ordering::Greater))
 }
 
 pub fn gt(_call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
-    Err(script_error!(
-        "comparison not implement for types: {:?}, {:?}",
-        lhs.value_type(),
-        rhs.value_type()
-    ))
+    Ok(Value::Boolean(lhs.compare(&rhs)? == Ordering::Greater))
 }
 
 pub fn lt(_call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
-    Err(script_error!(
-        "comparison not implement for types: {:?}, {:?}",
-        lhs.value_type(),
-        rhs.value_type()
+    Ok(Value::Boolean(lhs.compare(&rhs)? == Ordering::Less))
+}
+
+// collections
+
+fn expect_array(func_name: &str, value: Value) -> ScriptResult<Vec<Value>> {
+    match value {
+        Value::Array(items) => Ok(items),
+        value => Err(script_error!(
+            "{}: expected Array, found {}",
+            func_name,
+            value.value_type()
+        )),
+    }
+}
+
+fn expect_object(func_name: &str, value: Value) -> ScriptResult<BTreeMap<String, Value>> {
+    match value {
+        Value::Object(entries) => Ok(entries),
+        value => Err(script_error!(
+            "{}: expected Object, found {}",
+            func_name,
+            value.value_type()
+        )),
+    }
+}
+
+fn call2(scope: &Scope, func: &Value, arg0: Value, arg1: Value) -> ScriptResult<Value> {
+    func.invoke(scope.clone(), arg0)?
+        .invoke(scope.clone(), arg1)
+}
+
+fn sort_values<T>(items: &mut [(Value, T)]) -> ScriptResult<()> {
+    check_comparable(items.iter().map(|(value, _)| value).collect())?;
+    // all values are comparable, so this is a total order
+    items.sort_by(|(lhs, _), (rhs, _)| lhs.compare(rhs).unwrap_or(Ordering::Equal));
+    Ok(())
+}
+
+/// Check that values can be sorted: they must have the same type and numbers can't be NaN. The
+/// elements of arrays are checked the same way, position by position.
+fn check_comparable(values: Vec<&Value>) -> ScriptResult<()> {
+    let first = match values.first() {
+        Some(first) if values.len() > 1 => first.value_type(),
+        _ => return Ok(()),
+    };
+    let mut columns: Vec<Vec<&Value>> = Vec::new();
+    for value in values {
+        match value {
+            _ if value.value_type() != first => {
+                return Err(ScriptError::NotComparable(first, value.value_type()))
+            }
+            Value::Number(number) if number.is_nan() => {
+                return Err(ScriptError::NotComparable(first, first))
+            }
+            Value::Object(..) | Value::Function(..) => {
+                return Err(ScriptError::NotComparable(first, first))
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    match columns.get_mut(index) {
+                        Some(column) => column.push(item),
+                        None => columns.push(vec![item]),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    columns.into_iter().try_for_each(check_comparable)
+}
+
+pub fn map(call_scope: Scope, func: Value, collection: Value) -> ScriptResult<Value> {
+    match collection {
+        Value::Object(entries) => Ok(Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| Ok((key, func.invoke(call_scope.clone(), value)?)))
+                .collect::<ScriptResult<_>>()?,
+        )),
+        collection => Ok(Value::Array(
+            expect_array("map", collection)?
+                .into_iter()
+                .map(|item| func.invoke(call_scope.clone(), item))
+                .collect::<ScriptResult<_>>()?,
+        )),
+    }
+}
+
+pub fn filter(call_scope: Scope, func: Value, collection: Value) -> ScriptResult<Value> {
+    match collection {
+        Value::Object(entries) => {
+            let mut filtered = BTreeMap::new();
+            for (key, value) in entries {
+                if func.invoke(call_scope.clone(), value.clone())?.to_bool()? {
+                    filtered.insert(key, value);
+                }
+            }
+            Ok(Value::Object(filtered))
+        }
+        collection => {
+            let mut filtered = Vec::new();
+            for item in expect_array("filter", collection)? {
+                if func.invoke(call_scope.clone(), item.clone())?.to_bool()? {
+                    filtered.push(item);
+                }
+            }
+            Ok(Value::Array(filtered))
+        }
+    }
+}
+
+pub fn fold(call_scope: Scope, func: Value, init: Value, array: Value) -> ScriptResult<Value> {
+    let mut acc = init;
+    for item in expect_array("fold", array)? {
+        acc = call2(&call_scope, &func, acc, item)?;
+    }
+    Ok(acc)
+}
+
+pub fn reduce(call_scope: Scope, func: Value, array: Value) -> ScriptResult<Value> {
+    let mut items = expect_array("reduce", array)?.into_iter();
+    let mut acc = items
+        .next()
+        .ok_or_else(|| script_error!("reduce: cannot reduce empty array"))?;
+    for item in items {
+        acc = call2(&call_scope, &func, acc, item)?;
+    }
+    Ok(acc)
+}
+
+pub fn flat_map(call_scope: Scope, func: Value, array: Value) -> ScriptResult<Value> {
+    let mut items = Vec::new();
+    for item in expect_array("flat_map", array)? {
+        let value = func.invoke(call_scope.clone(), item)?;
+        items.extend(expect_array("flat_map", value)?);
+    }
+    Ok(Value::Array(items))
+}
+
+pub fn keys(_call_scope: Scope, object: Value) -> ScriptResult<Value> {
+    Ok(Value::new_array(
+        expect_object("keys", object)?
+            .into_keys()
+            .map(Value::String),
+    ))
+}
+
+pub fn values(_call_scope: Scope, object: Value) -> ScriptResult<Value> {
+    Ok(Value::new_array(
+        expect_object("values", object)?.into_values(),
+    ))
+}
+
+pub fn entries(_call_scope: Scope, object: Value) -> ScriptResult<Value> {
+    Ok(Value::new_array(
+        expect_object("entries", object)?
+            .into_iter()
+            .map(|(key, value)| Value::Array(vec![Value::String(key), value])),
     ))
 }
+
+pub fn from_entries(_call_scope: Scope, array: Value) -> ScriptResult<Value> {
+    let mut entries = BTreeMap::new();
+    for entry in expect_array("from_entries", array)? {
+        match entry {
+            Value::Array(pair) if pair.len() == 2 => {
+                let mut pair = pair.into_iter();
+                let key = pair.next().unwrap().to_string()?;
+                entries.insert(key, pair.next().unwrap());
+            }
+            entry => {
+                return Err(script_error!(
+                    "from_entries: expected [key, value] pair, found {}",
+                    entry.value_type()
+                ))
+            }
+        }
+    }
+    Ok(Value::Object(entries))
+}
+
+pub fn len(_call_scope: Scope, value: Value) -> ScriptResult<Value> {
+    let len = match &value {
+        Value::Array(items) => items.len(),
+        Value::Object(entries) => entries.len(),
+        Value::String(string) => string.chars().count(),
+        value => {
+            return Err(script_error!(
+                "len: expected Array, Object or String, found {}",
+                value.value_type()
+            ))
+        }
+    };
+    Ok(Value::Number(len as f64))
+}
+
+pub fn sort(_call_scope: Scope, array: Value) -> ScriptResult<Value> {
+    let mut items: Vec<_> = expect_array("sort", array)?
+        .into_iter()
+        .map(|item| (item, ()))
+        .collect();
+    sort_values(&mut items)?;
+    Ok(Value::new_array(items.into_iter().map(|(item, _)| item)))
+}
+
+pub fn sort_by(call_scope: Scope, func: Value, array: Value) -> ScriptResult<Value> {
+    let mut items = expect_array("sort_by", array)?
+        .into_iter()
+        .map(|item| Ok((func.invoke(call_scope.clone(), item.clone())?, item)))
+        .collect::<ScriptResult<Vec<_>>>()?;
+    sort_values(&mut items)?;
+    Ok(Value::new_array(items.into_iter().map(|(_, item)| item)))
+}
+
+pub fn unique(_call_scope: Scope, array: Value) -> ScriptResult<Value> {
+    let mut items: Vec<Value> = Vec::new();
+    for item in expect_array("unique", array)? {
+        let mut found = false;
+        for existing in &items {
+            if existing.equals(&item)? {
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            items.push(item);
+        }
+    }
+    Ok(Value::Array(items))
+}
+
+pub fn group_by(call_scope: Scope, func: Value, array: Value) -> ScriptResult<Value> {
+    let mut groups = BTreeMap::new();
+    for item in expect_array("group_by", array)? {
+        let key = func.invoke(call_scope.clone(), item.clone())?.to_string()?;
+        groups.entry(key).or_insert_with(Vec::new).push(item);
+    }
+    Ok(Value::new_object(
+        groups
+            .into_iter()
+            .map(|(key, items)| (key, Value::Array(items))),
+    ))
+}
+
+pub fn zip(_call_scope: Scope, lhs: Value, rhs: Value) -> ScriptResult<Value> {
+    let lhs = expect_array("zip", lhs)?;
+    let rhs = expect_array("zip", rhs)?;
+    Ok(Value::new_array(
+        lhs.into_iter()
+            .zip(rhs)
+            .map(|(lhs, rhs)| Value::Array(vec![lhs, rhs])),
+    ))
+}
+
+const MAX_SAFE_INTEGER: f64 = 9007199254740992.0;
+
+/// The largest array that `range` returns, so that a typo can't exhaust memory.
+const MAX_RANGE_LENGTH: f64 = 1e6;
+
+pub fn range(_call_scope: Scope, start: Value, end: Value) -> ScriptResult<Value> {
+    let (start, end) = match (&start, &end) {
+        (Value::Number(start), Value::Number(end)) => (*start, *end),
+        _ => {
+            return Err(script_error!(
+                "range: expected Number, Number, found {}, {}",
+                start.value_type(),
+                end.value_type()
+            ))
+        }
+    };
+    for bound in &[start, end] {
+        // larger numbers can't all be represented, so counting up to them might not end
+        if bound.fract() != 0.0 || bound.abs() > MAX_SAFE_INTEGER {
+            return Err(script_error!("range: expected integer, found {}", bound));
+        }
+    }
+    if end - start > MAX_RANGE_LENGTH {
+        return Err(script_error!(
+            "range: cannot create more than {} numbers, found {} to {}",
+            MAX_RANGE_LENGTH,
+            start,
+            end
+        ));
+    }
+
+    let mut items = Vec::new();
+    let mut value = start;
+    while value < end {
+        items.push(Value::Number(value));
+        value += 1.0;
+    }
+    Ok(Value::Array(items))
+}
+
+pub fn any(call_scope: Scope, func: Value, array: Value) -> ScriptResult<Value> {
+    for item in expect_array("any", array)? {
+        if func.invoke(call_scope.clone(), item)?.to_bool()? {
+            return Ok(Value::Boolean(true));
+        }
+    }
+    Ok(Value::Boolean(false))
+}
+
+pub fn all(call_scope: Scope, func: Value, array: Value) -> ScriptResult<Value> {
+    for item in expect_array("all", array)? {
+        if !func.invoke(call_scope.clone(), item)?.to_bool()? {
+            return Ok(Value::Boolean(false));
+        }
+    }
+    Ok(Value::Boolean(true))
+}
+
+pub fn find(call_scope: Scope, func: Value, array: Value) -> ScriptResult<Value> {
+    for item in expect_array("find", array)? {
+        if func.invoke(call_scope.clone(), item.clone())?.to_bool()? {
+            return Ok(item);
+        }
+    }
+    Ok(Value::Null)
+}
+
+pub fn index_of(_call_scope: Scope, value: Value, array: Value) -> ScriptResult<Value> {
+    for (index, item) in expect_array("index_of", array)?.iter().enumerate() {
+        if item.equals(&value)? {
+            return Ok(Value::Number(index as f64));
+        }
+    }
+    Ok(Value::Null)
+}
+
+pub fn reverse(_call_scope: Scope, value: Value) -> ScriptResult<Value> {
+    match value {
+        Value::String(string) => Ok(Value::String(string.chars().rev().collect())),
+        value => {
+            let mut items = expect_array("reverse", value)?;
+            items.reverse();
+            Ok(Value::Array(items))
+        }
+    }
+}
+
+pub fn take(_call_scope: Scope, count: Value, array: Value) -> ScriptResult<Value> {
+    let count = count.to_u32()? as usize;
+    let mut items = expect_array("take", array)?;
+    items.truncate(count);
+    Ok(Value::Array(items))
+}
+
+pub fn drop(_call_scope: Scope, count: Value, array: Value) -> ScriptResult<Value> {
+    let count = count.to_u32()? as usize;
+    let items = expect_array("drop", array)?;
+    Ok(Value::new_array(items.into_iter().skip(count)))
+}
+
+// strings
+
+fn expect_string(func_name: &str, value: Value) -> ScriptResult<String> {
+    match value {
+        Value::String(string) => Ok(string),
+        value => Err(script_error!(
+            "{}: expected String, found {}",
+            func_name,
+            value.value_type()
+        )),
+    }
+}
+
+fn expect_count(func_name: &str, arg: &str, value: Value) -> ScriptResult<usize> {
+    match value {
+        Value::Number(number) if number >= 0.0 && number.fract() == 0.0 => Ok(number as usize),
+        value => Err(ScriptError::InvalidArgumentType {
+            function: func_name.to_string(),
+            arg: arg.to_string(),
+            expected: "non-negative integer".to_string(),
+            actual: value.value_type(),
+        }),
+    }
+}
+
+pub fn split(_call_scope: Scope, separator: Value, string: Value) -> ScriptResult<Value> {
+    let separator = expect_string("split", separator)?;
+    let string = expect_string("split", string)?;
+    if separator.is_empty() {
+        return Err(script_error!("split: separator cannot be empty"));
+    }
+    Ok(Value::new_array(
+        string
+            .split(separator.as_str())
+            .map(|part| Value::String(part.to_string())),
+    ))
+}
+
+pub fn join(_call_scope: Scope, separator: Value, array: Value) -> ScriptResult<Value> {
+    let separator = expect_string("join", separator)?;
+    let parts = expect_array("join", array)?
+        .iter()
+        .map(Value::to_string)
+        .collect::<ScriptResult<Vec<_>>>()?;
+    Ok(Value::String(parts.join(&separator)))
+}
+
+pub fn trim(_call_scope: Scope, string: Value) -> ScriptResult<Value> {
+    let string = expect_string("trim", string)?;
+    Ok(Value::String(string.trim().to_string()))
+}
+
+pub fn upper(_call_scope: Scope, string: Value) -> ScriptResult<Value> {
+    let string = expect_string("upper", string)?;
+    Ok(Value::String(string.to_uppercase()))
+}
+
+pub fn lower(_call_scope: Scope, string: Value) -> ScriptResult<Value> {
+    let string = expect_string("lower", string)?;
+    Ok(Value::String(string.to_lowercase()))
+}
+
+pub fn replace(_call_scope: Scope, from: Value, to: Value, string: Value) -> ScriptResult<Value> {
+    let from = expect_string("replace", from)?;
+    let to = expect_string("replace", to)?;
+    let string = expect_string("replace", string)?;
+    if from.is_empty() {
+        return Err(script_error!("replace: pattern cannot be empty"));
+    }
+    Ok(Value::String(string.replace(&from, &to)))
+}
+
+pub fn starts_with(_call_scope: Scope, prefix: Value, string: Value) -> ScriptResult<Value> {
+    let prefix = expect_string("starts_with", prefix)?;
+    let string = expect_string("starts_with", string)?;
+    Ok(Value::Boolean(string.starts_with(&prefix)))
+}
+
+pub fn ends_with(_call_scope: Scope, suffix: Value, string: Value) -> ScriptResult<Value> {
+    let suffix = expect_string("ends_with", suffix)?;
+    let string = expect_string("ends_with", string)?;
+    Ok(Value::Boolean(string.ends_with(&suffix)))
+}
+
+pub fn contains(_call_scope: Scope, needle: Value, haystack: Value) -> ScriptResult<Value> {
+    match haystack {
+        Value::Array(items) => {
+            for item in &items {
+                if item.equals(&needle)? {
+                    return Ok(Value::Boolean(true));
+                }
+            }
+            Ok(Value::Boolean(false))
+        }
+        haystack => {
+            let needle = expect_string("contains", needle)?;
+            let haystack = expect_string("contains", haystack)?;
+            Ok(Value::Boolean(haystack.contains(&needle)))
+        }
+    }
+}
+
+pub fn substring(
+    _call_scope: Scope,
+    start: Value,
+    end: Value,
+    string: Value,
+) -> ScriptResult<Value> {
+    let start = expect_count("substring", "start", start)?;
+    let end = expect_count("substring", "end", end)?;
+    let string = expect_string("substring", string)?;
+    Ok(Value::String(
+        string
+            .chars()
+            .skip(start)
+            .take(end.saturating_sub(start))
+            .collect(),
+    ))
+}
+
+/// The longest string, in characters, that `repeat` and padding return, so that a typo can't exhaust
+/// memory.
+const MAX_STRING_LENGTH: usize = 10_000_000;
+
+fn check_length(func_name: &str, length: usize) -> ScriptResult<()> {
+    if length > MAX_STRING_LENGTH {
+        return Err(script_error!(
+            "{}: cannot create a string of more than {} characters, found {}",
+            func_name,
+            MAX_STRING_LENGTH,
+            length
+        ));
+    }
+    Ok(())
+}
+
+fn padding(func_name: &str, width: Value, fill: Value, string: &str) -> ScriptResult<String> {
+    let width = expect_count(func_name, "width", width)?;
+    let fill = expect_string(func_name, fill)?;
+    let mut fill_chars = fill.chars();
+    let fill = match (fill_chars.next(), fill_chars.next()) {
+        (Some(fill), None) => fill,
+        _ => {
+            return Err(script_error!(
+                "{}: fill must be a single character, found {:?}",
+                func_name,
+                fill
+            ))
+        }
+    };
+
+    let len = string.chars().count();
+    if width > len {
+        check_length(func_name, width)?;
+    }
+    Ok(std::iter::repeat_n(fill, width.saturating_sub(len)).collect())
+}
+
+pub fn pad_left(
+    _call_scope: Scope,
+    width: Value,
+    fill: Value,
+    string: Value,
+) -> ScriptResult<Value> {
+    let string = string.to_string()?;
+    let padding = padding("pad_left", width, fill, &string)?;
+    Ok(Value::String(padding + &string))
+}
+
+pub fn pad_right(
+    _call_scope: Scope,
+    width: Value,
+    fill: Value,
+    string: Value,
+) -> ScriptResult<Value> {
+    let string = string.to_string()?;
+    let padding = padding("pad_right", width, fill, &string)?;
+    Ok(Value::String(string + &padding))
+}
+
+pub fn repeat(_call_scope: Scope, count: Value, string: Value) -> ScriptResult<Value> {
+    let count = expect_count("repeat", "count", count)?;
+    let string = expect_string("repeat", string)?;
+    check_length("repeat", string.chars().count().saturating_mul(count))?;
+    Ok(Value::String(string.repeat(count)))
+}
+
+pub fn chars(_call_scope: Scope, string: Value) -> ScriptResult<Value> {
+    let string = expect_string("chars", string)?;
+    Ok(Value::new_array(
+        string.chars().map(|ch| Value::String(ch.to_string())),
+    ))
+}
+
+/// Substitute `{}` placeholders with items from an array (in order), or `{name}` placeholders with
+/// entries from an object. Literal braces are written as `{{` and `}}`.
+pub fn format(_call_scope: Scope, template: Value, args: Value) -> ScriptResult<Value> {
+    let template = expect_string("format", template)?;
+
+    let mut output = String::new();
+    let mut index = 0;
+    let mut chars = template.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let rest = chars.as_str();
+                let end = rest.find('}').ok_or_else(|| {
+                    script_error!("format: unclosed placeholder in {:?}", template)
+                })?;
+                let name = &rest[..end];
+                chars = rest[end + 1..].chars();
+
+                let value = match (&args, name) {
+                    (Value::Array(items), "") => {
+                        index += 1;
+                        items.get(index - 1).ok_or_else(|| {
+                            script_error!("format: not enough arguments for {:?}", template)
+                        })?
+                    }
+                    (Value::Object(entries), name) if !name.is_empty() => {
+                        entries.get(name).ok_or_else(|| {
+                            ScriptError::PropertyNotFound(args.value_type(), name.to_string())
+                        })?
+                    }
+                    (args, name) => {
+                        return Err(script_error!(
+                            "format: cannot substitute placeholder {{{}}} from {}",
+                            name,
+                            args.value_type()
+                        ))
+                    }
+                };
+                output.push_str(&value.to_string()?);
+            }
+            '}' => return Err(script_error!("format: unmatched '}}' in {:?}", template)),
+            ch => output.push(ch),
+        }
+    }
+
+    Ok(Value::String(output))
+}
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+
+use crate::error::*;
+use crate::value::{Function, Value, ValueType};
+
+/// A Rust type that a value can be converted to, for the parameters of functions registered with
+/// [`Function::from_fn`].
+pub trait FromValue: Sized {
+    /// Describe the values that can be converted, like `[Number]`.
+    fn expected() -> String;
+
+    /// Convert a value, or return `None` if it doesn't have the expected type.
+    fn from_value(value: Value) -> Option<Self>;
+}
+
+impl FromValue for Value {
+    fn expected() -> String {
+        "any value".to_string()
+    }
+
+    fn from_value(value: Value) -> Option<Value> {
+        Some(value)
+    }
+}
+
+impl FromValue for f64 {
+    fn expected() -> String {
+        ValueType::Number.to_string()
+    }
+
+    fn from_value(value: Value) -> Option<f64> {
+        match value {
+            Value::Number(number) => Some(number),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for String {
+    fn expected() -> String {
+        ValueType::String.to_string()
+    }
+
+    fn from_value(value: Value) -> Option<String> {
+        match value {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn expected() -> String {
+        ValueType::Boolean.to_string()
+    }
+
+    fn from_value(value: Value) -> Option<bool> {
+        match value {
+            Value::Boolean(boolean) => Some(boolean),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn expected() -> String {
+        format!("[{}]", T::expected())
+    }
+
+    fn from_value(value: Value) -> Option<Vec<T>> {
+        match value {
+            Value::Array(items) => items.into_iter().map(T::from_value).collect(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for BTreeMap<String, T> {
+    fn expected() -> String {
+        format!("Object of {}", T::expected())
+    }
+
+    fn from_value(value: Value) -> Option<BTreeMap<String, T>> {
+        match value {
+            Value::Object(entries) => entries
+                .into_iter()
+                .map(|(key, value)| Some((key, T::from_value(value)?)))
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+/// `null`, including a missing argument, is converted to `None`.
+impl<T: FromValue> FromValue for Option<T> {
+    fn expected() -> String {
+        format!("{} or Null", T::expected())
+    }
+
+    fn from_value(value: Value) -> Option<Option<T>> {
+        match value {
+            Value::Null | Value::PropagatedNull => Some(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl From<f64> for Value {
+    fn from(number: f64) -> Value {
+        Value::Number(number)
+    }
+}
+
+impl From<String> for Value {
+    fn from(string: String) -> Value {
+        Value::String(string)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(string: &str) -> Value {
+        Value::String(string.to_string())
+    }
+}
+
+impl From<bool> for Value {
+    fn from(boolean: bool) -> Value {
+        Value::Boolean(boolean)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Value {
+        Value::new_array(items.into_iter().map(Into::into))
+    }
+}
+
+impl<T: Into<Value>> From<BTreeMap<String, T>> for Value {
+    fn from(entries: BTreeMap<String, T>) -> Value {
+        Value::new_object(entries.into_iter().map(|(key, value)| (key, value.into())))
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Value {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+/// The return type of a function registered with [`Function::from_fn`], which is either a type
+/// that can be converted to a value or a [`ScriptResult`] of one.
+pub trait NativeResult {
+    fn into_result(self) -> ScriptResult<Value>;
+}
+
+impl<T: Into<Value>> NativeResult for T {
+    fn into_result(self) -> ScriptResult<Value> {
+        Ok(self.into())
+    }
+}
+
+impl<T: Into<Value>> NativeResult for ScriptResult<T> {
+    fn into_result(self) -> ScriptResult<Value> {
+        self.map(Into::into)
+    }
+}
+
+/// A Rust function with one to three parameters that can be called from scripts. `Args` is the
+/// tuple of the parameter types.
+pub trait NativeFn<Args>: 'static + Send + Sync {
+    /// The number of parameters.
+    const ARITY: usize;
+
+    /// Create a curried function, which converts its arguments when it is called with all of them.
+    fn into_function(self, name: &str, params: &[&str]) -> Function;
+}
+
+/// Convert an argument of a native function, failing with an error that names the function and
+/// the parameter.
+fn convert<T: FromValue>(function: &str, param: &str, value: Value) -> ScriptResult<T> {
+    let actual = value.value_type();
+    T::from_value(value).ok_or_else(|| ScriptError::InvalidArgumentType {
+        function: function.to_string(),
+        arg: param.to_string(),
+        expected: T::expected(),
+        actual,
+    })
+}
+
+macro_rules! impl_native_fn {
+    ($arity:literal, $constructor:ident, $($ty:ident $arg:ident $index:tt),*) => {
+        impl<F, R, $($ty),*> NativeFn<($($ty,)*)> for F
+        where
+            F: 'static + Send + Sync + Fn($($ty),*) -> R,
+            R: NativeResult,
+            $($ty: FromValue),*
+        {
+            const ARITY: usize = $arity;
+
+            fn into_function(self, name: &str, params: &[&str]) -> Function {
+                let name = name.to_string();
+                let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+                Function::$constructor(move |_, $($arg),*| {
+                    self($(convert(&name, &params[$index], $arg)?),*).into_result()
+                })
+            }
+        }
+    };
+}
+
+impl_native_fn!(1, new, A a 0);
+impl_native_fn!(2, new2, A a 0, B b 1);
+impl_native_fn!(3, new3, A a 0, B b 1, C c 2);
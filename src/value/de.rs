@@ -0,0 +1,429 @@
+use std::collections::{btree_map, BTreeMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::vec;
+
+use serde::de::value::StringDeserializer;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess,
+    SeqAccess, VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+use crate::error::*;
+use crate::value::ser::MAX_SAFE_INTEGER;
+use crate::value::Value;
+
+/// Convert a value to a Rust type.
+///
+/// Errors include the path of the value that could not be converted, like
+/// `spec.ports[2].port: expected integer, found String`.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> ScriptResult<T> {
+    T::deserialize(value)
+}
+
+impl<'de> de::Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "any value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Boolean(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Number(value as f64))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::Number(value as f64))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(Value::Number(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        de::Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut entries = BTreeMap::new();
+        while let Some((key, value)) = map.next_entry()? {
+            entries.insert(key, value);
+        }
+        Ok(Value::Object(entries))
+    }
+}
+
+fn invalid(expected: &str, value: &Value) -> ScriptError {
+    de::Error::custom(format_args!(
+        "expected {}, found {}",
+        expected,
+        value.value_type()
+    ))
+}
+
+/// Convert a number to an integer type, if it is an integer in the range of the type.
+fn to_integer<T: TryFrom<i128> + fmt::Display>(value: &Value, min: T, max: T) -> ScriptResult<T> {
+    let out_of_range = |number: f64| {
+        de::Error::custom(format_args!(
+            "expected integer between {} and {}, found {}",
+            min, max, number
+        ))
+    };
+    match value {
+        Value::Number(number) if number.fract() != 0.0 || !number.is_finite() => Err(
+            de::Error::custom(format_args!("expected integer, found {}", number)),
+        ),
+        // casting to i128 saturates, so larger numbers are rejected first
+        Value::Number(number) if number.abs() >= 2f64.powi(127) => Err(out_of_range(*number)),
+        Value::Number(number) => T::try_from(*number as i128).map_err(|_| out_of_range(*number)),
+        value => Err(invalid("integer", value)),
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident => $visit:ident($ty:ty),)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+                visitor.$visit(to_integer::<$ty>(&self, <$ty>::MIN, <$ty>::MAX)?)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = ScriptError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+        match self {
+            Value::Object(entries) => visitor.visit_map(ObjectAccess::new(entries)),
+            Value::Array(items) => visitor.visit_seq(ArrayAccess::new(items)),
+            // integers are visited as integers, so that they can be converted to integer types
+            Value::Number(number)
+                if number.fract() == 0.0 && number.abs() <= MAX_SAFE_INTEGER as f64 =>
+            {
+                if number < 0.0 {
+                    visitor.visit_i64(number as i64)
+                } else {
+                    visitor.visit_u64(number as u64)
+                }
+            }
+            Value::Number(number) => visitor.visit_f64(number),
+            Value::String(string) => visitor.visit_string(string),
+            Value::Boolean(boolean) => visitor.visit_bool(boolean),
+            Value::Null | Value::PropagatedNull => visitor.visit_unit(),
+            Value::Function(..) => Err(de::Error::custom("cannot convert a function")),
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+        match self {
+            Value::Number(number) => visitor.visit_f64(number),
+            value => Err(invalid("number", &value)),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+        match self {
+            Value::Boolean(boolean) => visitor.visit_bool(boolean),
+            value => Err(invalid("boolean", &value)),
+        }
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+        match self {
+            Value::String(string) => visitor.visit_string(string),
+            value => Err(invalid("string", &value)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+        match self {
+            Value::Null | Value::PropagatedNull => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+        match self {
+            Value::Null | Value::PropagatedNull => visitor.visit_unit(),
+            value => Err(invalid("null", &value)),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> ScriptResult<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> ScriptResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+        match self {
+            Value::Array(items) => visitor.visit_seq(ArrayAccess::new(items)),
+            value => Err(invalid("array", &value)),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> ScriptResult<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> ScriptResult<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+        match self {
+            Value::Object(entries) => visitor.visit_map(ObjectAccess::new(entries)),
+            value => Err(invalid("object", &value)),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> ScriptResult<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    /// Unit variants are strings, and other variants are objects with a single entry, like
+    /// `{ tcp: 80 }`.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> ScriptResult<V::Value> {
+        match self {
+            Value::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Value::Object(entries) if entries.len() == 1 => {
+                let (variant, value) = entries.into_iter().next().unwrap();
+                visitor
+                    .visit_enum(VariantValue {
+                        variant: variant.clone(),
+                        value,
+                    })
+                    .map_err(|err| err.in_key(&variant))
+            }
+            value => Err(invalid("string or object with a single entry", &value)),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> ScriptResult<V::Value> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bytes byte_buf identifier
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ScriptError> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+struct ArrayAccess {
+    items: vec::IntoIter<Value>,
+    index: usize,
+}
+
+impl ArrayAccess {
+    fn new(items: Vec<Value>) -> ArrayAccess {
+        ArrayAccess {
+            items: items.into_iter(),
+            index: 0,
+        }
+    }
+}
+
+impl<'de> SeqAccess<'de> for ArrayAccess {
+    type Error = ScriptError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> ScriptResult<Option<T::Value>> {
+        match self.items.next() {
+            Some(item) => {
+                let index = self.index;
+                self.index += 1;
+                seed.deserialize(item)
+                    .map(Some)
+                    .map_err(|err| err.in_index(index))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct ObjectAccess {
+    entries: btree_map::IntoIter<String, Value>,
+    next: Option<(String, Value)>,
+}
+
+impl ObjectAccess {
+    fn new(entries: BTreeMap<String, Value>) -> ObjectAccess {
+        ObjectAccess {
+            entries: entries.into_iter(),
+            next: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for ObjectAccess {
+    type Error = ScriptError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> ScriptResult<Option<K::Value>> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                let result = seed
+                    .deserialize(key.as_str().into_deserializer())
+                    .map_err(|err: ScriptError| err.in_key(&key));
+                self.next = Some((key, value));
+                result.map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> ScriptResult<V::Value> {
+        let (key, value) = self
+            .next
+            .take()
+            .ok_or_else(|| <ScriptError as de::Error>::custom("value requested before key"))?;
+        seed.deserialize(value).map_err(|err| err.in_key(&key))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct VariantValue {
+    variant: String,
+    value: Value,
+}
+
+impl<'de> EnumAccess<'de> for VariantValue {
+    type Error = ScriptError;
+    type Variant = Value;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> ScriptResult<(V::Value, Value)> {
+        let deserializer: StringDeserializer<ScriptError> = self.variant.into_deserializer();
+        let variant = seed.deserialize(deserializer)?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Value {
+    type Error = ScriptError;
+
+    fn unit_variant(self) -> ScriptResult<()> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> ScriptResult<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> ScriptResult<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> ScriptResult<V::Value> {
+        self.deserialize_map(visitor)
+    }
+}
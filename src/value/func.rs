@@ -5,70 +5,80 @@ use crate::ast::{Expr, FnArg};
 use crate::error::*;
 use crate::eval::Evaluate;
 use crate::scope::Scope;
-use crate::value::Value;
+use crate::value::{NativeFn, TypeDescriptor, Value, ValueType};
 
 #[derive(Clone, Debug)]
 pub enum Function {
-    Code(Scope, Option<FnArg>, Box<Expr>),
-    Nested(Scope, Option<FnArg>, Arc<Function>),
+    Code(Scope, Param, Box<Expr>),
+    Nested(Scope, Param, Arc<Function>),
     Native(NativeFunction),
+    Type(ValueType),
 }
 
 impl Function {
     pub fn invoke(&self, call_scope: Scope, arg: Value) -> ScriptResult<Value> {
         match self {
-            Function::Code(scope, fn_arg, expr) => {
-                let mut scope = scope.clone();
-                if let Some(fn_arg) = fn_arg {
-                    scope = scope.set(&fn_arg.name.value, arg)?;
-                }
-                expr.evaluate_value(scope)
+            Function::Code(scope, param, expr) => {
+                let scope = param
+                    .bind(scope.clone(), call_scope, arg)
+                    .map_err(|err| err.in_call(param.call_frame(None)))?;
+                expr.evaluate_value(scope.clone())
+                    .map_err(|err| err.in_call(param.call_frame(Some(&scope))))
             }
-            Function::Nested(scope, fn_arg, func) => {
-                let mut scope = scope.clone();
-                if let Some(fn_arg) = fn_arg {
-                    scope = scope.set(&fn_arg.name.value, arg)?;
-                }
+            Function::Nested(scope, param, func) => {
+                let scope = param
+                    .bind(scope.clone(), call_scope, arg)
+                    .map_err(|err| err.in_call(param.call_frame(None)))?;
                 Ok(func.extend_scope(scope).into())
             }
             Function::Native(func) => (func.0)(call_scope, arg),
+            Function::Type(value_type) => Ok(Value::Boolean(arg.value_type() == *value_type)),
         }
     }
 
-    pub fn new_from_expr(scope: &Scope, arg: Option<FnArg>, expr: Expr) -> Function {
-        Function::Code(scope.inherit(), arg, Box::new(expr))
+    pub fn new_from_expr(
+        scope: &Scope,
+        name: Option<&str>,
+        arg: Option<FnArg>,
+        expr: Expr,
+    ) -> ScriptResult<Function> {
+        let param = Param::new(scope, name, arg)?;
+        Ok(Function::Code(scope.inherit(), param, Box::new(expr)))
     }
 
-    pub fn new_nested(scope: &Scope, arg: Option<FnArg>, nested: Function) -> Function {
-        Function::Nested(scope.inherit(), arg, Arc::new(nested))
+    pub fn new_nested(
+        scope: &Scope,
+        name: Option<&str>,
+        arg: Option<FnArg>,
+        nested: Function,
+    ) -> ScriptResult<Function> {
+        let param = Param::new(scope, name, arg)?;
+        Ok(Function::Nested(scope.inherit(), param, Arc::new(nested)))
     }
 
     fn extend_scope(&self, scope: Scope) -> Function {
         match self {
-            Function::Code(old_scope, fn_arg, expr) => Function::Code(
-                old_scope.clone().extend(scope),
-                fn_arg.clone(),
-                expr.clone(),
-            ),
-            Function::Nested(old_scope, fn_arg, func) => Function::Nested(
-                old_scope.clone().extend(scope),
-                fn_arg.clone(),
-                func.clone(),
-            ),
+            Function::Code(old_scope, param, expr) => {
+                Function::Code(old_scope.clone().extend(scope), param.clone(), expr.clone())
+            }
+            Function::Nested(old_scope, param, func) => {
+                Function::Nested(old_scope.clone().extend(scope), param.clone(), func.clone())
+            }
             Function::Native(func) => Function::Native(func.clone()),
+            Function::Type(value_type) => Function::Type(*value_type),
         }
     }
 
     pub fn new<F>(f: F) -> Function
     where
-        F: 'static + Fn(Scope, Value) -> ScriptResult<Value>,
+        F: 'static + Send + Sync + Fn(Scope, Value) -> ScriptResult<Value>,
     {
         Function::Native(NativeFunction(Arc::new(f)))
     }
 
     pub fn new2<F>(f: F) -> Function
     where
-        F: 'static + Fn(Scope, Value, Value) -> ScriptResult<Value>,
+        F: 'static + Send + Sync + Fn(Scope, Value, Value) -> ScriptResult<Value>,
     {
         let f = Arc::new(f);
         Function::new(move |_: Scope, arg0: Value| {
@@ -79,7 +89,7 @@ impl Function {
 
     pub fn new3<F>(f: F) -> Function
     where
-        F: 'static + Fn(Scope, Value, Value, Value) -> ScriptResult<Value>,
+        F: 'static + Send + Sync + Fn(Scope, Value, Value, Value) -> ScriptResult<Value>,
     {
         let f = Arc::new(f);
         Function::new(move |_: Scope, arg0: Value| {
@@ -97,14 +107,103 @@ impl Function {
     }
 }
 
+impl Function {
+    /// Create a function from a Rust function with typed parameters, which are converted from the
+    /// arguments when the function is called. Functions with several parameters are curried, like
+    /// functions defined in scripts.
+    ///
+    /// `name` and `params` are used in the errors of converting arguments, like `Invalid argument
+    /// port to function url: expected Number, found String`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameter names doesn't match the number of parameters of `f`.
+    pub fn from_fn<Args, F: NativeFn<Args>>(name: &str, params: &[&str], f: F) -> Function {
+        assert_eq!(
+            params.len(),
+            F::ARITY,
+            "function {} has {} parameters, but {} names were given",
+            name,
+            F::ARITY,
+            params.len()
+        );
+        f.into_function(name, params)
+    }
+}
+
 impl From<Function> for Value {
     fn from(func: Function) -> Value {
         Value::Function(func)
     }
 }
 
+/// The parameter of a function defined in code, with its type annotation evaluated.
+#[derive(Clone, Debug)]
+pub struct Param {
+    pub func_name: Option<String>,
+    pub name: Option<String>,
+    pub ty: Option<TypeDescriptor>,
+}
+
+impl Param {
+    fn new(scope: &Scope, func_name: Option<&str>, arg: Option<FnArg>) -> ScriptResult<Param> {
+        let (name, ty) = match arg {
+            Some(arg) => {
+                let ty = arg
+                    .ty
+                    .map(|ty| TypeDescriptor::from_value(ty.evaluate_value(scope.clone())?))
+                    .transpose()?;
+                (Some(arg.name.value), ty)
+            }
+            None => (None, None),
+        };
+
+        Ok(Param {
+            func_name: func_name.map(str::to_string),
+            name,
+            ty,
+        })
+    }
+
+    /// Describe a call to this function. The argument is looked up in the scope it was bound to,
+    /// so that it only needs to be summarized when an error occurs.
+    fn call_frame(&self, scope: Option<&Scope>) -> CallFrame {
+        let args = match (&self.name, scope) {
+            (Some(name), Some(scope)) => match scope.get(name) {
+                Ok(arg) => format!("{} = {}", name, arg.summary()),
+                Err(_) => name.clone(),
+            },
+            (Some(name), None) => name.clone(),
+            (None, _) => String::new(),
+        };
+        CallFrame {
+            function: self.func_name.as_deref().unwrap_or("<lambda>").to_string(),
+            args,
+            span: None,
+        }
+    }
+
+    fn bind(&self, scope: Scope, call_scope: Scope, arg: Value) -> ScriptResult<Scope> {
+        if let Some(ty) = &self.ty {
+            if !ty.check(call_scope, &arg)? {
+                return Err(ScriptError::InvalidArgumentType {
+                    function: self.func_name.as_deref().unwrap_or("<lambda>").to_string(),
+                    arg: self.name.clone().unwrap_or_default(),
+                    expected: ty.to_string(),
+                    actual: arg.value_type(),
+                });
+            }
+        }
+
+        match &self.name {
+            Some(name) => scope.set(name, arg),
+            None => Ok(scope),
+        }
+    }
+}
+
 #[derive(Clone)]
-pub struct NativeFunction(Arc<dyn Fn(Scope, Value) -> ScriptResult<Value>>);
+pub struct NativeFunction(Arc<dyn Fn(Scope, Value) -> ScriptResult<Value> + Send + Sync>);
 
 impl Debug for NativeFunction {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
@@ -0,0 +1,125 @@
+use std::io::Write;
+
+use serde_json::{Map, Value as Json};
+
+use crate::error::*;
+use crate::value::{json_type, Value};
+
+impl Value {
+    /// Write this value as an INI file, which must be an object.
+    ///
+    /// Objects in the top-level object are written as sections, and must only contain values that
+    /// are not objects or arrays. Other values in the top-level object are written before the
+    /// first section.
+    pub fn to_ini(&self) -> ScriptResult<String> {
+        let value = self.to_json()?;
+        let map = match &value {
+            Json::Object(map) => map,
+            value => {
+                return Err(not_representable(
+                    "",
+                    format!(
+                        "the top-level value must be an object, found {}",
+                        json_type(value)
+                    ),
+                ))
+            }
+        };
+
+        let mut out = String::new();
+        for (key, value) in map {
+            if !matches!(value, Json::Object(..)) {
+                write_entry(&mut out, key, key, value)?;
+            }
+        }
+        for (name, value) in map {
+            if let Json::Object(section) = value {
+                write_section(&mut out, name, section)?;
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn write_ini_to<W: Write>(&self, mut writer: W) -> ScriptResult<()> {
+        writer.write_all(self.to_ini()?.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn write_section(out: &mut String, name: &str, section: &Map<String, Json>) -> ScriptResult<()> {
+    if name.is_empty() || name.contains(&['[', ']', '\n', '\r'][..]) {
+        return Err(not_representable(name, "invalid section name".to_string()));
+    }
+
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push('[');
+    out.push_str(name);
+    out.push_str("]\n");
+
+    for (key, value) in section {
+        write_entry(out, &format!("{}.{}", name, key), key, value)?;
+    }
+    Ok(())
+}
+
+fn write_entry(out: &mut String, path: &str, key: &str, value: &Json) -> ScriptResult<()> {
+    let invalid_key = key.is_empty()
+        || key.starts_with(&[';', '#', '['][..])
+        || key.contains(&['=', ':', '\n', '\r'][..])
+        || key.trim() != key;
+    if invalid_key {
+        return Err(not_representable(path, "invalid key".to_string()));
+    }
+
+    let value = match value {
+        Json::String(string) if string.contains(&['\n', '\r'][..]) => {
+            return Err(not_representable(
+                path,
+                "strings with several lines are not supported".to_string(),
+            ))
+        }
+        Json::String(string) if string.trim() != string => {
+            return Err(not_representable(
+                path,
+                "strings with leading or trailing whitespace are not supported".to_string(),
+            ))
+        }
+        Json::String(string) => string.clone(),
+        Json::Number(number) => match number.as_f64() {
+            Some(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+                (number as i64).to_string()
+            }
+            _ => number.to_string(),
+        },
+        Json::Bool(b) => b.to_string(),
+        Json::Object(..) => {
+            return Err(not_representable(
+                path,
+                "only one level of sections is supported".to_string(),
+            ))
+        }
+        Json::Array(..) => {
+            return Err(not_representable(
+                path,
+                "arrays are not supported".to_string(),
+            ))
+        }
+        Json::Null => return Err(not_representable(path, "null is not supported".to_string())),
+    };
+
+    out.push_str(key);
+    out.push_str(" = ");
+    out.push_str(&value);
+    out.push('\n');
+    Ok(())
+}
+
+fn not_representable(path: &str, reason: String) -> ScriptError {
+    ScriptError::NotRepresentable {
+        format: "INI".to_string(),
+        path: path.to_string(),
+        reason,
+    }
+}
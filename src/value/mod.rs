@@ -1,12 +1,25 @@
+mod convert;
+mod de;
 mod func;
+mod ini;
+mod ser;
+mod toml;
+mod types;
+mod yaml;
 
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::fmt::{self, Debug, Display};
 
 use crate::error::*;
 use crate::scope::Scope;
 
+pub use convert::{FromValue, NativeFn, NativeResult};
+pub use de::from_value;
 pub use func::Function;
+pub use ser::to_value;
+pub use types::TypeDescriptor;
+pub use yaml::YamlOptions;
 
 #[derive(Clone, Debug)]
 pub enum Value {
@@ -56,7 +69,7 @@ impl Value {
         match self {
             Value::Function(func) => Ok(func.invoke(scope, arg)?.simplify()),
             Value::PropagatedNull => Ok(Value::PropagatedNull),
-            value @ _ => Err(ScriptError::NotCallable(value.value_type())),
+            value => Err(ScriptError::NotCallable(value.value_type())),
         }
     }
 
@@ -116,26 +129,34 @@ impl Value {
     pub fn simplify(self) -> Value {
         match self {
             Value::Null | Value::PropagatedNull => Value::Null,
-            value @ _ => value,
+            value => value,
         }
     }
 
     pub fn or_propagated_null(self) -> Value {
         match self {
             Value::Null | Value::PropagatedNull => Value::PropagatedNull,
-            value @ _ => value,
+            value => value,
         }
     }
 
     pub fn to_string(&self) -> ScriptResult<String> {
         match self {
-            Value::Number(value) => Ok(format!("{}", value)),
-            Value::String(value) => Ok(format!("{}", value)),
-            Value::Boolean(true) => Ok(format!("true")),
-            Value::Boolean(false) => Ok(format!("false")),
-            Value::Null => Ok(format!("null")),
+            Value::Number(value) => Ok(value.to_string()),
+            Value::String(value) => Ok(value.to_string()),
+            Value::Boolean(true) => Ok("true".to_string()),
+            Value::Boolean(false) => Ok("false".to_string()),
+            Value::Null => Ok("null".to_string()),
 
-            value @ _ => Err(ScriptError::NotStringConvertible(value.value_type())),
+            value => Err(ScriptError::NotStringConvertible(value.value_type())),
+        }
+    }
+
+    pub fn to_bool(&self) -> ScriptResult<bool> {
+        match self {
+            Value::Boolean(value) => Ok(*value),
+
+            value => Err(ScriptError::NotBoolConvertible(value.value_type())),
         }
     }
 
@@ -146,7 +167,86 @@ impl Value {
                 .map_err(|_| script_error!("cannot convert string {:?} to integer", value))?),
             Value::Number(value) => Ok(*value as u32), // check value bounds
 
-            value @ _ => Err(ScriptError::NotIntConvertible(value.value_type())),
+            value => Err(ScriptError::NotIntConvertible(value.value_type())),
+        }
+    }
+
+    /// Check if two values are structurally equal.
+    ///
+    /// Values of different types are never equal, and propagated nulls are equal to `null`.
+    /// Functions cannot be compared with each other.
+    pub fn equals(&self, other: &Value) -> ScriptResult<bool> {
+        match (self, other) {
+            (Value::Object(lhs), Value::Object(rhs)) => {
+                if lhs.len() != rhs.len() {
+                    return Ok(false);
+                }
+                for ((lhs_key, lhs_value), (rhs_key, rhs_value)) in lhs.iter().zip(rhs.iter()) {
+                    if lhs_key != rhs_key || !lhs_value.equals(rhs_value)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+
+            (Value::Array(lhs), Value::Array(rhs)) => {
+                if lhs.len() != rhs.len() {
+                    return Ok(false);
+                }
+                for (lhs, rhs) in lhs.iter().zip(rhs.iter()) {
+                    if !lhs.equals(rhs)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+
+            (Value::Number(lhs), Value::Number(rhs)) => Ok(lhs == rhs),
+            (Value::String(lhs), Value::String(rhs)) => Ok(lhs == rhs),
+            (Value::Boolean(lhs), Value::Boolean(rhs)) => Ok(lhs == rhs),
+            (Value::Null, Value::Null)
+            | (Value::Null, Value::PropagatedNull)
+            | (Value::PropagatedNull, Value::Null)
+            | (Value::PropagatedNull, Value::PropagatedNull) => Ok(true),
+
+            (Value::Function(..), Value::Function(..)) => Err(ScriptError::NotComparable(
+                self.value_type(),
+                other.value_type(),
+            )),
+
+            _ => Ok(false),
+        }
+    }
+
+    /// Compare the ordering of two values.
+    ///
+    /// Numbers are compared numerically, strings and arrays lexicographically, and `false` is less
+    /// than `true`. Values of different types, objects and functions cannot be ordered.
+    pub fn compare(&self, other: &Value) -> ScriptResult<Ordering> {
+        let not_comparable = || ScriptError::NotComparable(self.value_type(), other.value_type());
+
+        match (self, other) {
+            (Value::Array(lhs), Value::Array(rhs)) => {
+                for (lhs, rhs) in lhs.iter().zip(rhs.iter()) {
+                    match lhs.compare(rhs)? {
+                        Ordering::Equal => (),
+                        ordering => return Ok(ordering),
+                    }
+                }
+                Ok(lhs.len().cmp(&rhs.len()))
+            }
+
+            (Value::Number(lhs), Value::Number(rhs)) => {
+                lhs.partial_cmp(rhs).ok_or_else(not_comparable)
+            }
+            (Value::String(lhs), Value::String(rhs)) => Ok(lhs.cmp(rhs)),
+            (Value::Boolean(lhs), Value::Boolean(rhs)) => Ok(lhs.cmp(rhs)),
+            (Value::Null, Value::Null)
+            | (Value::Null, Value::PropagatedNull)
+            | (Value::PropagatedNull, Value::Null)
+            | (Value::PropagatedNull, Value::PropagatedNull) => Ok(Ordering::Equal),
+
+            _ => Err(not_comparable()),
         }
     }
 
@@ -187,6 +287,43 @@ impl Value {
         }
     }
 
+    pub fn from_json(value: serde_json::Value) -> Value {
+        match value {
+            serde_json::Value::Object(map) => Value::new_object(
+                map.into_iter()
+                    .map(|(key, value)| (key, Value::from_json(value))),
+            ),
+            serde_json::Value::Array(array) => {
+                Value::new_array(array.into_iter().map(Value::from_json))
+            }
+            serde_json::Value::Number(number) => Value::Number(number.as_f64().unwrap_or(f64::NAN)),
+            serde_json::Value::String(string) => Value::String(string),
+            serde_json::Value::Bool(b) => Value::Boolean(b),
+            serde_json::Value::Null => Value::Null,
+        }
+    }
+
+    /// Return a short, single-line description of this value for error messages.
+    pub(crate) fn summary(&self) -> String {
+        const MAX_LEN: usize = 40;
+
+        let json = match self {
+            Value::Function(..) => None,
+            value => value.to_json_opt().ok().flatten(),
+        };
+        let summary = match json {
+            Some(json) => json.to_string(),
+            None => "<function>".to_string(),
+        };
+
+        if summary.chars().count() > MAX_LEN {
+            let truncated: String = summary.chars().take(MAX_LEN - 3).collect();
+            format!("{}...", truncated)
+        } else {
+            summary
+        }
+    }
+
     pub fn to_json(&self) -> ScriptResult<serde_json::Value> {
         Ok(self.to_json_opt()?.unwrap_or(serde_json::Value::Null))
     }
@@ -218,7 +355,7 @@ impl Value {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ValueType {
     Object,
     Array,
@@ -229,8 +366,35 @@ pub enum ValueType {
     Function,
 }
 
+impl ValueType {
+    pub fn from_name(name: &str) -> Option<ValueType> {
+        match name {
+            "object" => Some(ValueType::Object),
+            "array" => Some(ValueType::Array),
+            "number" => Some(ValueType::Number),
+            "string" => Some(ValueType::String),
+            "boolean" => Some(ValueType::Boolean),
+            "null" => Some(ValueType::Null),
+            "function" => Some(ValueType::Function),
+            _ => None,
+        }
+    }
+}
+
 impl Display for ValueType {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         write!(fmt, "{:?}", self)
     }
 }
+
+/// Return the type of a JSON value.
+fn json_type(value: &serde_json::Value) -> ValueType {
+    match value {
+        serde_json::Value::Object(..) => ValueType::Object,
+        serde_json::Value::Array(..) => ValueType::Array,
+        serde_json::Value::Number(..) => ValueType::Number,
+        serde_json::Value::String(..) => ValueType::String,
+        serde_json::Value::Bool(..) => ValueType::Boolean,
+        serde_json::Value::Null => ValueType::Null,
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fmt::Display;
+
+use serde::ser::{self, Serialize, Serializer};
+
+use crate::error::*;
+use crate::value::Value;
+
+/// Convert a Rust value to a value.
+///
+/// Enums are converted like [`from_value`](crate::from_value) reads them: unit variants become
+/// strings, and other variants become objects with a single entry.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> ScriptResult<Value> {
+    value.serialize(ValueSerializer)
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Object(entries) => serializer.collect_map(entries),
+            Value::Array(items) => serializer.collect_seq(items),
+            // integers are written without a fractional part
+            Value::Number(number)
+                if number.fract() == 0.0 && number.abs() <= MAX_SAFE_INTEGER as f64 =>
+            {
+                serializer.serialize_i64(*number as i64)
+            }
+            Value::Number(number) => serializer.serialize_f64(*number),
+            Value::String(string) => serializer.serialize_str(string),
+            Value::Boolean(boolean) => serializer.serialize_bool(*boolean),
+            Value::Null | Value::PropagatedNull => serializer.serialize_unit(),
+            Value::Function(..) => Err(ser::Error::custom("cannot serialize a function")),
+        }
+    }
+}
+
+/// Integers with a larger magnitude can't always be represented exactly as numbers.
+pub(super) const MAX_SAFE_INTEGER: u128 = 1 << 53;
+
+fn integer(value: impl TryInto<i128> + Display + Copy) -> ScriptResult<Value> {
+    match value.try_into() {
+        Ok(integer) if integer.unsigned_abs() <= MAX_SAFE_INTEGER => {
+            Ok(Value::Number(integer as f64))
+        }
+        _ => Err(ser::Error::custom(format_args!(
+            "integer {} is too large to be represented exactly",
+            value
+        ))),
+    }
+}
+
+/// Write a variant that is not a unit variant, as an object with a single entry.
+fn variant_object(variant: &str, value: Value) -> Value {
+    Value::new_object(vec![(variant.to_string(), value)])
+}
+
+struct ValueSerializer;
+
+impl Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = ScriptError;
+
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeVariant<SerializeArray>;
+    type SerializeMap = SerializeObject;
+    type SerializeStruct = SerializeObject;
+    type SerializeStructVariant = SerializeVariant<SerializeObject>;
+
+    fn serialize_bool(self, value: bool) -> ScriptResult<Value> {
+        Ok(Value::Boolean(value))
+    }
+
+    fn serialize_i8(self, value: i8) -> ScriptResult<Value> {
+        integer(value)
+    }
+
+    fn serialize_i16(self, value: i16) -> ScriptResult<Value> {
+        integer(value)
+    }
+
+    fn serialize_i32(self, value: i32) -> ScriptResult<Value> {
+        integer(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> ScriptResult<Value> {
+        integer(value)
+    }
+
+    fn serialize_i128(self, value: i128) -> ScriptResult<Value> {
+        integer(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> ScriptResult<Value> {
+        integer(value)
+    }
+
+    fn serialize_u16(self, value: u16) -> ScriptResult<Value> {
+        integer(value)
+    }
+
+    fn serialize_u32(self, value: u32) -> ScriptResult<Value> {
+        integer(value)
+    }
+
+    fn serialize_u64(self, value: u64) -> ScriptResult<Value> {
+        integer(value)
+    }
+
+    fn serialize_u128(self, value: u128) -> ScriptResult<Value> {
+        integer(value)
+    }
+
+    fn serialize_f32(self, value: f32) -> ScriptResult<Value> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn serialize_f64(self, value: f64) -> ScriptResult<Value> {
+        Ok(Value::Number(value))
+    }
+
+    fn serialize_char(self, value: char) -> ScriptResult<Value> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn serialize_str(self, value: &str) -> ScriptResult<Value> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> ScriptResult<Value> {
+        Ok(Value::new_array(
+            value.iter().map(|byte| Value::Number((*byte).into())),
+        ))
+    }
+
+    fn serialize_none(self) -> ScriptResult<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> ScriptResult<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> ScriptResult<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> ScriptResult<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> ScriptResult<Value> {
+        Ok(Value::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> ScriptResult<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> ScriptResult<Value> {
+        let value = value.serialize(self).map_err(|err| err.in_key(variant))?;
+        Ok(variant_object(variant, value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> ScriptResult<SerializeArray> {
+        Ok(SerializeArray {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> ScriptResult<SerializeArray> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> ScriptResult<SerializeArray> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> ScriptResult<SerializeVariant<SerializeArray>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> ScriptResult<SerializeObject> {
+        Ok(SerializeObject {
+            entries: BTreeMap::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> ScriptResult<SerializeObject> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> ScriptResult<SerializeVariant<SerializeObject>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+struct SerializeArray {
+    items: Vec<Value>,
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = Value;
+    type Error = ScriptError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> ScriptResult<()> {
+        let index = self.items.len();
+        let value = to_value(value).map_err(|err| err.in_index(index))?;
+        self.items.push(value);
+        Ok(())
+    }
+
+    fn end(self) -> ScriptResult<Value> {
+        Ok(Value::Array(self.items))
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = Value;
+    type Error = ScriptError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> ScriptResult<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> ScriptResult<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = Value;
+    type Error = ScriptError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> ScriptResult<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> ScriptResult<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct SerializeObject {
+    entries: BTreeMap<String, Value>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeObject {
+    type Ok = Value;
+    type Error = ScriptError;
+
+    /// Keys must be strings, but numbers and booleans are converted to strings like in JSON.
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> ScriptResult<()> {
+        let key = match to_value(key)? {
+            Value::String(key) => key,
+            value @ Value::Number(..) | value @ Value::Boolean(..) => value.to_string()?,
+            value => {
+                return Err(ser::Error::custom(format_args!(
+                    "keys must be strings, found {}",
+                    value.value_type()
+                )))
+            }
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> ScriptResult<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| <ScriptError as ser::Error>::custom("value serialized before key"))?;
+        let value = to_value(value).map_err(|err| err.in_key(&key))?;
+        self.entries.insert(key, value);
+        Ok(())
+    }
+
+    fn end(self) -> ScriptResult<Value> {
+        Ok(Value::Object(self.entries))
+    }
+}
+
+impl ser::SerializeStruct for SerializeObject {
+    type Ok = Value;
+    type Error = ScriptError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> ScriptResult<()> {
+        let value = to_value(value).map_err(|err| err.in_key(key))?;
+        self.entries.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    fn end(self) -> ScriptResult<Value> {
+        Ok(Value::Object(self.entries))
+    }
+}
+
+struct SerializeVariant<T> {
+    variant: &'static str,
+    inner: T,
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeArray> {
+    type Ok = Value;
+    type Error = ScriptError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> ScriptResult<()> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+            .map_err(|err| err.in_key(self.variant))
+    }
+
+    fn end(self) -> ScriptResult<Value> {
+        let value = ser::SerializeSeq::end(self.inner)?;
+        Ok(variant_object(self.variant, value))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeObject> {
+    type Ok = Value;
+    type Error = ScriptError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> ScriptResult<()> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+            .map_err(|err| err.in_key(self.variant))
+    }
+
+    fn end(self) -> ScriptResult<Value> {
+        let value = ser::SerializeStruct::end(self.inner)?;
+        Ok(variant_object(self.variant, value))
+    }
+}
@@ -0,0 +1,207 @@
+use std::io::Write;
+
+use serde_json::{Map, Number, Value as Json};
+
+use crate::error::*;
+use crate::value::{json_type, Value};
+
+impl Value {
+    /// Write this value as a TOML document, which must be an object.
+    ///
+    /// Objects are written as tables and arrays of objects as arrays of tables, except when they
+    /// are nested in other arrays, where they are written as inline tables.
+    pub fn to_toml(&self) -> ScriptResult<String> {
+        let value = self.to_json()?;
+        let map = match &value {
+            Json::Object(map) => map,
+            value => {
+                return Err(not_representable(
+                    "",
+                    format!(
+                        "the top-level value must be an object, found {}",
+                        json_type(value)
+                    ),
+                ))
+            }
+        };
+
+        let mut out = String::new();
+        write_table_body(&mut out, &mut Vec::new(), "", map)?;
+        Ok(out)
+    }
+
+    pub fn write_toml_to<W: Write>(&self, mut writer: W) -> ScriptResult<()> {
+        writer.write_all(self.to_toml()?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Write the key/value pairs of a table, followed by its sub-tables and arrays of tables. `keys`
+/// are the keys of the table for headers, and `path` is its path for error messages.
+fn write_table_body(
+    out: &mut String,
+    keys: &mut Vec<String>,
+    path: &str,
+    map: &Map<String, Json>,
+) -> ScriptResult<()> {
+    // values have to come before any table header, which would start a new table
+    for (key, value) in map {
+        if !is_table(value) && !is_array_of_tables(value) {
+            write_key(out, key);
+            out.push_str(" = ");
+            write_inline(out, &child_path(path, key), value)?;
+            out.push('\n');
+        }
+    }
+
+    for (key, value) in map {
+        let path = child_path(path, key);
+        keys.push(key.clone());
+        match value {
+            Json::Object(map) if is_table(value) => {
+                write_header(out, keys, "[", "]");
+                write_table_body(out, keys, &path, map)?;
+            }
+            Json::Array(items) if is_array_of_tables(value) => {
+                for (i, item) in items.iter().enumerate() {
+                    write_header(out, keys, "[[", "]]");
+                    if let Json::Object(map) = item {
+                        write_table_body(out, keys, &format!("{}[{}]", path, i), map)?;
+                    }
+                }
+            }
+            _ => (),
+        }
+        keys.pop();
+    }
+
+    Ok(())
+}
+
+fn write_header(out: &mut String, keys: &[String], open: &str, close: &str) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(open);
+    for (i, key) in keys.iter().enumerate() {
+        if i > 0 {
+            out.push('.');
+        }
+        write_key(out, key);
+    }
+    out.push_str(close);
+    out.push('\n');
+}
+
+/// Write a value on a single line, with objects as inline tables.
+fn write_inline(out: &mut String, path: &str, value: &Json) -> ScriptResult<()> {
+    match value {
+        Json::Object(map) => {
+            out.push('{');
+            for (i, (key, value)) in map.iter().enumerate() {
+                out.push_str(if i > 0 { ", " } else { " " });
+                write_key(out, key);
+                out.push_str(" = ");
+                write_inline(out, &child_path(path, key), value)?;
+            }
+            out.push_str(if map.is_empty() { "}" } else { " }" });
+        }
+        Json::Array(items) => {
+            if let Some(first) = items.first() {
+                if let Some(item) = items
+                    .iter()
+                    .find(|item| json_type(item) != json_type(first))
+                {
+                    return Err(not_representable(
+                        path,
+                        format!(
+                            "arrays must not mix types, found {} and {}",
+                            json_type(first),
+                            json_type(item)
+                        ),
+                    ));
+                }
+            }
+
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_inline(out, &format!("{}[{}]", path, i), item)?;
+            }
+            out.push(']');
+        }
+        Json::Number(number) => write_number(out, number),
+        Json::String(string) => write_string(out, string),
+        Json::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Json::Null => return Err(not_representable(path, "null is not supported".to_string())),
+    }
+    Ok(())
+}
+
+fn write_number(out: &mut String, number: &Number) {
+    match number.as_f64() {
+        Some(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+            out.push_str(&(number as i64).to_string())
+        }
+        // unlike `Display`, `Debug` always writes a fraction or an exponent
+        Some(number) => out.push_str(&format!("{:?}", number)),
+        None => out.push_str(&number.to_string()),
+    }
+}
+
+fn write_key(out: &mut String, key: &str) {
+    let is_bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if is_bare {
+        out.push_str(key);
+    } else {
+        write_string(out, key);
+    }
+}
+
+fn write_string(out: &mut String, string: &str) {
+    out.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn is_table(value: &Json) -> bool {
+    matches!(value, Json::Object(..))
+}
+
+fn is_array_of_tables(value: &Json) -> bool {
+    match value {
+        Json::Array(items) => !items.is_empty() && items.iter().all(is_table),
+        _ => false,
+    }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn not_representable(path: &str, reason: String) -> ScriptError {
+    ScriptError::NotRepresentable {
+        format: "TOML".to_string(),
+        path: path.to_string(),
+        reason,
+    }
+}
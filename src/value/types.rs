@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+use crate::error::*;
+use crate::scope::Scope;
+use crate::value::{Function, Value, ValueType};
+
+/// A type that values can be checked against, such as the type annotation of a function argument.
+#[derive(Clone, Debug)]
+pub enum TypeDescriptor {
+    Type(ValueType),
+    Predicate(Box<Function>),
+    Array(Box<TypeDescriptor>),
+    Object(BTreeMap<String, TypeDescriptor>),
+}
+
+impl TypeDescriptor {
+    /// Create a type descriptor from a value.
+    ///
+    /// Built-in types (like `number`) and type names (like `"number"`) describe values of that
+    /// type, and other functions are used as predicates. An array with a single item describes
+    /// arrays where every item matches that item, and an object describes objects where every
+    /// key matches the corresponding value.
+    pub fn from_value(value: Value) -> ScriptResult<TypeDescriptor> {
+        match value {
+            Value::Function(Function::Type(value_type)) => Ok(TypeDescriptor::Type(value_type)),
+            Value::Function(func) => Ok(TypeDescriptor::Predicate(Box::new(func))),
+
+            Value::String(name) => ValueType::from_name(&name)
+                .map(TypeDescriptor::Type)
+                .ok_or_else(|| script_error!("Unknown type name: {}", name)),
+
+            Value::Null | Value::PropagatedNull => Ok(TypeDescriptor::Type(ValueType::Null)),
+
+            Value::Array(mut items) => {
+                if items.len() != 1 {
+                    return Err(script_error!(
+                        "Array type must have exactly one item type, found {}",
+                        items.len()
+                    ));
+                }
+                let item = TypeDescriptor::from_value(items.remove(0))?;
+                Ok(TypeDescriptor::Array(Box::new(item)))
+            }
+
+            Value::Object(entries) => Ok(TypeDescriptor::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, TypeDescriptor::from_value(value)?)))
+                    .collect::<ScriptResult<_>>()?,
+            )),
+
+            value => Err(script_error!("Cannot use {} as a type", value.value_type())),
+        }
+    }
+
+    /// Check if a value matches this type.
+    pub fn check(&self, scope: Scope, value: &Value) -> ScriptResult<bool> {
+        match self {
+            TypeDescriptor::Type(value_type) => Ok(value.value_type() == *value_type),
+
+            TypeDescriptor::Predicate(func) => func.invoke(scope, value.clone())?.to_bool(),
+
+            TypeDescriptor::Array(item_type) => match value {
+                Value::Array(items) => {
+                    for item in items {
+                        if !item_type.check(scope.clone(), item)? {
+                            return Ok(false);
+                        }
+                    }
+                    Ok(true)
+                }
+                _ => Ok(false),
+            },
+
+            TypeDescriptor::Object(entry_types) => match value {
+                Value::Object(entries) => {
+                    for (key, entry_type) in entry_types {
+                        let entry = entries.get(key).unwrap_or(&Value::Null);
+                        if !entry_type.check(scope.clone(), entry)? {
+                            return Ok(false);
+                        }
+                    }
+                    Ok(true)
+                }
+                _ => Ok(false),
+            },
+        }
+    }
+}
+
+impl Display for TypeDescriptor {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeDescriptor::Type(value_type) => write!(fmt, "{}", value_type),
+            TypeDescriptor::Predicate(..) => write!(fmt, "value matching predicate"),
+            TypeDescriptor::Array(item_type) => write!(fmt, "[{}]", item_type),
+            TypeDescriptor::Object(entry_types) => {
+                write!(fmt, "{{ ")?;
+                for (i, (key, entry_type)) in entry_types.iter().enumerate() {
+                    if i > 0 {
+                        write!(fmt, ", ")?;
+                    }
+                    write!(fmt, "{:?}: {}", key, entry_type)?;
+                }
+                write!(fmt, " }}")
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::io::Write;
+
+use serde_json::{Map, Number, Value as Json};
+
+use crate::error::*;
+use crate::value::Value;
+
+/// Options for writing values as YAML.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct YamlOptions {
+    /// Write collections in flow style (`{a: [1, 2]}`) instead of block style.
+    pub flow: bool,
+    /// Write every item of a top-level array as a separate document.
+    pub multi_document: bool,
+}
+
+impl Value {
+    pub fn to_yaml(&self, options: YamlOptions) -> ScriptResult<String> {
+        let value = self.to_json()?;
+        let mut out = String::new();
+
+        match value {
+            Json::Array(items) if options.multi_document => {
+                for item in &items {
+                    out.push_str("---\n");
+                    write_document(&mut out, item, options);
+                }
+            }
+            value => write_document(&mut out, &value, options),
+        }
+
+        Ok(out)
+    }
+
+    pub fn write_yaml_to<W: Write>(&self, mut writer: W, options: YamlOptions) -> ScriptResult<()> {
+        writer.write_all(self.to_yaml(options)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn write_document(out: &mut String, value: &Json, options: YamlOptions) {
+    if options.flow {
+        write_flow(out, value);
+        out.push('\n');
+    } else {
+        write_block(out, value, 0, true, "");
+    }
+}
+
+/// Write a value in block style after an indicator (`key:` or `-`), separated by `sep`.
+///
+/// Nested collections start on the next line with indentation `indent`, unless `inline` is set, in
+/// which case their first entry is written on the current line.
+fn write_block(out: &mut String, value: &Json, indent: usize, inline: bool, sep: &str) {
+    match value {
+        Json::Object(map) if !map.is_empty() => {
+            if inline {
+                out.push_str(sep);
+            } else {
+                out.push('\n');
+                push_indent(out, indent);
+            }
+            write_mapping(out, map, indent);
+        }
+        Json::Array(items) if !items.is_empty() => {
+            if inline {
+                out.push_str(sep);
+            } else {
+                out.push('\n');
+                push_indent(out, indent);
+            }
+            write_sequence(out, items, indent);
+        }
+        Json::String(string) if is_block_literal(string) => {
+            out.push_str(sep);
+            write_block_literal(out, string, indent.max(2));
+        }
+        value => {
+            out.push_str(sep);
+            write_flow(out, value);
+            out.push('\n');
+        }
+    }
+}
+
+/// Write a mapping in block style. The indentation of the first line must already be written.
+fn write_mapping(out: &mut String, map: &Map<String, Json>, indent: usize) {
+    for (i, (key, value)) in map.iter().enumerate() {
+        if i > 0 {
+            push_indent(out, indent);
+        }
+        write_string(out, key, false);
+        out.push(':');
+        write_block(out, value, indent + 2, false, " ");
+    }
+}
+
+/// Write a sequence in block style. The indentation of the first line must already be written.
+fn write_sequence(out: &mut String, items: &[Json], indent: usize) {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            push_indent(out, indent);
+        }
+        out.push('-');
+        write_block(out, item, indent + 2, true, " ");
+    }
+}
+
+fn write_block_literal(out: &mut String, string: &str, indent: usize) {
+    let body = string.trim_end_matches('\n');
+    let trailing_newlines = string.len() - body.len();
+
+    out.push_str(match trailing_newlines {
+        0 => "|-",
+        1 => "|",
+        _ => "|+",
+    });
+    out.push('\n');
+
+    for line in body.split('\n') {
+        if !line.is_empty() {
+            push_indent(out, indent);
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    for _ in 1..trailing_newlines {
+        out.push('\n');
+    }
+}
+
+fn write_flow(out: &mut String, value: &Json) {
+    match value {
+        Json::Object(map) => {
+            out.push('{');
+            for (i, (key, value)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_string(out, key, true);
+                out.push_str(": ");
+                write_flow(out, value);
+            }
+            out.push('}');
+        }
+        Json::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_flow(out, item);
+            }
+            out.push(']');
+        }
+        Json::Number(number) => write_number(out, number),
+        Json::String(string) => write_string(out, string, true),
+        Json::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Json::Null => out.push_str("null"),
+    }
+}
+
+fn write_number(out: &mut String, number: &Number) {
+    match number.as_f64() {
+        // integers are written without a fractional part, so that they are read as integers
+        Some(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+            out.push_str(&(number as i64).to_string())
+        }
+        Some(number) => {
+            let number = format!("{:?}", number);
+            match number.split_once('e') {
+                // YAML 1.1 needs a fraction and a signed exponent, like `1.0e+20`
+                Some((mantissa, exponent)) => {
+                    out.push_str(mantissa);
+                    if !mantissa.contains('.') {
+                        out.push_str(".0");
+                    }
+                    out.push('e');
+                    if !exponent.starts_with('-') {
+                        out.push('+');
+                    }
+                    out.push_str(exponent);
+                }
+                None => out.push_str(&number),
+            }
+        }
+        None => out.push_str(&number.to_string()),
+    }
+}
+
+fn write_string(out: &mut String, string: &str, flow: bool) {
+    if needs_quotes(string, flow) {
+        // JSON strings are valid double-quoted YAML scalars
+        out.push_str(&Json::String(string.to_string()).to_string());
+    } else {
+        out.push_str(string);
+    }
+}
+
+/// Return whether a string must be quoted, because it would not be read back as the same string
+/// otherwise.
+fn needs_quotes(string: &str, flow: bool) -> bool {
+    const RESERVED: &[&str] = &[
+        "null", "~", "true", "false", "yes", "no", "on", "off", "y", "n", ".nan", ".inf", "-.inf",
+        "+.inf", "<<", "=",
+    ];
+    const INDICATORS: &[char] = &[
+        '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@',
+        '`',
+    ];
+
+    let mut chars = string.chars();
+    let (first, second) = match (chars.next(), chars.next()) {
+        (Some(first), second) => (first, second),
+        (None, _) => return true,
+    };
+
+    RESERVED.contains(&string.to_ascii_lowercase().as_str())
+        || INDICATORS.contains(&first)
+        // anything that looks like a number or a date
+        || first.is_ascii_digit()
+        || (matches!(first, '+' | '.') && matches!(second, Some(c) if c.is_ascii_digit() || c == '.'))
+        || string.starts_with(char::is_whitespace)
+        || string.ends_with(char::is_whitespace)
+        || string.ends_with(':')
+        || string.contains(": ")
+        || string.contains(" #")
+        || string.chars().any(is_special_char)
+        || (flow && string.contains(&[',', '[', ']', '{', '}', ':', '?'][..]))
+}
+
+/// Return whether a string can be written as a block literal (`|`), which is used for multi-line
+/// strings.
+fn is_block_literal(string: &str) -> bool {
+    string.contains('\n')
+        && !string.trim_end_matches('\n').is_empty()
+        && !string.chars().any(|c| c != '\n' && c != '\t' && is_special_char(c))
+        // the indentation of the block is taken from its first line
+        && !string
+            .split('\n')
+            .find(|line| !line.is_empty())
+            .is_some_and(|line| line.starts_with(char::is_whitespace))
+}
+
+fn is_special_char(c: char) -> bool {
+    c.is_control() || matches!(c, '\u{feff}' | '\u{2028}' | '\u{2029}')
+}
+
+fn push_indent(out: &mut String, indent: usize) {
+    out.push_str(&" ".repeat(indent));
+}
@@ -0,0 +1,122 @@
+use jjay::*;
+
+/// Check a script, returning the code and location of every diagnostic.
+fn check(source: &str) -> Vec<(&'static str, usize, usize)> {
+    jjay::check_script(source, &Scope::new_default())
+        .into_iter()
+        .map(|diagnostic| {
+            (
+                diagnostic.code,
+                diagnostic.span.line,
+                diagnostic.span.column,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn check_clean() {
+    assert_eq!(
+        check("let f(x)(y: number) = [x, y] | map((v => v * 2)); let s = `${f(1)(2)}`; s"),
+        vec![]
+    );
+    assert_eq!(check("let _unused = 1; (let x = 2; local_scope())"), vec![]);
+}
+
+#[test]
+fn check_undefined() {
+    // functions can't refer to themselves or to variables defined after them
+    assert_eq!(
+        check("let f(x) = [x, y]; let y = 3; f(y)"),
+        vec![("undefined-variable", 1, 16)]
+    );
+    assert_eq!(
+        check("let f(x) = f(x); f(1)"),
+        vec![("undefined-variable", 1, 12)]
+    );
+    assert_eq!(
+        check("(let x = 1; x) + x"),
+        vec![("undefined-variable", 1, 18)]
+    );
+}
+
+#[test]
+fn check_duplicate() {
+    assert_eq!(
+        check("let x = 1; let x = 2; x"),
+        vec![("duplicate-definition", 1, 16)]
+    );
+    assert_eq!(
+        check("match [1, 2] case [a, a] then a end"),
+        vec![("duplicate-definition", 1, 23)]
+    );
+    assert_eq!(
+        check("let f(x)(x) = 3; f(1)(2)"),
+        vec![("unused-parameter", 1, 7), ("duplicate-definition", 1, 10)]
+    );
+}
+
+#[test]
+fn check_unused() {
+    assert_eq!(
+        check("let x = 1; let f(a)(b) = a; f"),
+        vec![("unused-variable", 1, 5), ("unused-parameter", 1, 21)]
+    );
+    assert_eq!(
+        check("match {} case { name, ...rest } then name end"),
+        vec![("unused-variable", 1, 26)]
+    );
+}
+
+#[test]
+fn check_shadowing() {
+    assert_eq!(
+        check("let x = 1; (let x = 2; x) + x"),
+        vec![("shadowed-variable", 1, 17)]
+    );
+    assert_eq!(
+        check("[1] | map((map => map))"),
+        vec![("shadowed-variable", 1, 12)]
+    );
+}
+
+#[test]
+fn check_not_callable() {
+    assert_eq!(
+        check(r#"[1(2), "a"(), {}(1), null(), null?()]"#),
+        vec![
+            ("not-callable", 1, 2),
+            ("not-callable", 1, 8),
+            ("not-callable", 1, 15),
+            ("not-callable", 1, 22),
+        ]
+    );
+    assert_eq!(
+        check("let null = (x => x); null(1)"),
+        vec![("shadowed-variable", 1, 5)]
+    );
+}
+
+#[test]
+fn check_unreachable_case() {
+    assert_eq!(
+        check(r#"match 1 case x if x > 0 then x case _ then 0 case 1 then 1 end"#),
+        vec![("unreachable-case", 1, 46)]
+    );
+    assert_eq!(
+        check(r#"match 1 case "a" then 0 case n: number then n case 2 then 1 case "a" then 2 end"#),
+        vec![("unreachable-case", 1, 47), ("unreachable-case", 1, 61)]
+    );
+}
+
+#[test]
+fn check_syntax_error() {
+    let diagnostics = jjay::check_script("let x = ;\nx", &Scope::new_default());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, "syntax-error");
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(
+        (diagnostics[0].span.line, diagnostics[0].span.column),
+        (1, 9)
+    );
+}
@@ -0,0 +1,348 @@
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn data_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/cli_data")
+        .join(name)
+}
+
+fn run_jjay(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_jjay"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // jjay may exit before reading its input, so errors writing it are ignored
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+fn assert_output(args: &[&str], stdin: &str, expected: &str) {
+    let output = run_jjay(args, stdin);
+    assert!(
+        output.status.success(),
+        "jjay failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let actual: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let expected: serde_json::Value = serde_json::from_str(expected).unwrap();
+    assert_eq!(actual, expected);
+}
+
+fn assert_error(args: &[&str], stdin: &str, expected: &str) {
+    let output = run_jjay(args, stdin);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(expected), "unexpected error:\n{}", stderr);
+}
+
+#[test]
+fn cli_args() {
+    let motd = data_path("motd.txt");
+    assert_output(
+        &[
+            "--arg",
+            "env",
+            "prod",
+            "--arg-json",
+            "replicas",
+            "[1, 2]",
+            "--arg-file",
+            "motd",
+            motd.to_str().unwrap(),
+            "-",
+        ],
+        "{ env: env, replicas: replicas, motd: motd }",
+        r#"{"env": "prod", "replicas": [1.0, 2.0], "motd": "hello\n"}"#,
+    );
+}
+
+#[test]
+fn cli_arg_builtin() {
+    assert_error(
+        &["--arg", "map", "x", "-"],
+        "map",
+        "Variable already exists: map",
+    );
+}
+
+#[test]
+fn cli_arg_import_builtin() {
+    assert_error(
+        &["--arg", "read_csv", "x", "-"],
+        "read_csv",
+        "Variable already exists: read_csv",
+    );
+}
+
+#[test]
+fn cli_arg_duplicate() {
+    assert_error(
+        &["--arg", "env", "x", "--arg-json", "env", "1", "-"],
+        "env",
+        "Variable already exists: env",
+    );
+}
+
+#[test]
+fn cli_no_filesystem() {
+    let motd = data_path("motd.txt");
+    let args = [
+        "--no-filesystem",
+        "--arg",
+        "path",
+        motd.to_str().unwrap(),
+        "-",
+    ];
+    assert_error(&args, "read_yaml(path)", "filesystem access is disabled");
+    // the import functions of outer scopes can't be reached through scope()
+    assert_error(
+        &args,
+        "scope().read_yaml(path)",
+        "filesystem access is disabled",
+    );
+    assert_error(&args, r#"scope()["/import_str"](path)"#, "/import_str");
+    assert_output(&args[1..], "scope().read_yaml(path)", r#""hello""#);
+}
+
+#[test]
+fn cli_arg_invalid_json() {
+    assert_error(
+        &["--arg-json", "x", "[1", "-"],
+        "x",
+        "Invalid JSON for argument x",
+    );
+}
+
+fn run_lines(args: &[&str], stdin: &str) -> Vec<serde_json::Value> {
+    let output = run_jjay(args, stdin);
+    assert!(
+        output.status.success(),
+        "jjay failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn cli_input() {
+    let script = data_path("env.jjay");
+    assert_output(
+        &[
+            "--input",
+            "-",
+            "--arg",
+            "env",
+            "prod",
+            script.to_str().unwrap(),
+        ],
+        r#"{"name": "web"}"#,
+        r#"{"env": "prod", "name": "web"}"#,
+    );
+    // the input is read from a file here, so the script can be read from stdin
+    let services = data_path("services.ndjson");
+    let lines = run_lines(
+        &["--input", services.to_str().unwrap(), "--ndjson", "-"],
+        "`${input.name}:${input.port}`",
+    );
+    assert_eq!(lines, vec!["web:80", "db:5432"]);
+}
+
+#[test]
+fn cli_input_apply() {
+    let script = data_path("port.jjay");
+    let lines = run_lines(
+        &["-i", "-", "--apply", "--ndjson", script.to_str().unwrap()],
+        "{\"port\": 80}\n{\"port\": 443}\n",
+    );
+    assert_eq!(
+        lines,
+        vec![serde_json::json!(81.0), serde_json::json!(444.0)]
+    );
+}
+
+#[test]
+fn cli_input_errors() {
+    assert_error(
+        &["-i", "-", "-"],
+        "input",
+        "Cannot read both the script and input from standard input",
+    );
+    let script = data_path("port.jjay");
+    assert_error(
+        &["-i", "-", "--apply", script.to_str().unwrap()],
+        "{\"port\": 80}\n{",
+        "Invalid JSON in -",
+    );
+}
+
+#[test]
+fn cli_output_yaml() {
+    let output = run_jjay(
+        &["--output", "yaml", "--multi-document", "-"],
+        "[{ kind: \"Service\" }, { kind: \"Deployment\", replicas: 2 }]",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "---\nkind: Service\n---\nkind: Deployment\nreplicas: 2\n"
+    );
+}
+
+#[test]
+fn cli_multi() {
+    let dir = std::env::temp_dir().join(format!("jjay-cli-multi-{}", std::process::id()));
+    let script = r#"{
+        "service.json": { port: 80 },
+        "k8s/deploy.yaml": { kind: "Deployment" },
+        "app.toml": { name: "web" },
+        "motd.txt": "hello\n",
+    }"#;
+
+    let output = run_jjay(&["-m", dir.to_str().unwrap(), "--dry-run", "-"], script);
+    assert!(output.status.success());
+    assert!(!dir.exists());
+    let listing = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(listing.lines().count(), 4);
+    assert!(listing.contains("deploy.yaml (17 bytes)"));
+
+    let output = run_jjay(&["-m", dir.to_str().unwrap(), "-"], script);
+    assert!(output.status.success());
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    assert_eq!(read("service.json"), "{\n  \"port\": 80.0\n}\n");
+    assert_eq!(read("k8s/deploy.yaml"), "kind: Deployment\n");
+    assert_eq!(read("app.toml"), "name = \"web\"\n");
+    assert_eq!(read("motd.txt"), "hello\n");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_multi_errors() {
+    let dir = std::env::temp_dir().join(format!("jjay-cli-multi-errors-{}", std::process::id()));
+    let dir = dir.to_str().unwrap();
+    assert_error(
+        &["-m", dir, "-"],
+        r#"{ "../escape.json": 1 }"#,
+        "paths must be relative and stay within the output directory",
+    );
+    assert_error(
+        &["-m", dir, "-"],
+        r#"{ "/etc/escape.json": 1 }"#,
+        "paths must be relative and stay within the output directory",
+    );
+    assert_error(
+        &["-m", dir, "-"],
+        r#"{ "a.bin": 1 }"#,
+        "unknown file extension",
+    );
+    assert_error(
+        &["-m", dir, "-"],
+        r#"{ "a.txt": 1 }"#,
+        "expected String, found Number",
+    );
+    assert_error(
+        &["-m", dir, "-"],
+        r#"{ "a.json": 1, "./a.json": 2 }"#,
+        r#"Cannot write "a.json": "./a.json" has the same path"#,
+    );
+    assert!(!std::path::Path::new(dir).exists());
+}
+
+#[test]
+fn cli_repl() {
+    let output = run_jjay(
+        &["repl"],
+        concat!(
+            "let x = 1;\n",
+            "let double(n) = (\n",
+            "  n * 2\n",
+            ");\n",
+            "double(x)\n",
+            ":type double\n",
+            "let x = 2;\n",
+            "`x = ${x}`\n",
+            ":scope\n",
+            "missing\n",
+        ),
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "2.0\nfunction\n\"x = 2\"\ndouble: function\nx: number\n"
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Variable not found: missing"));
+}
+
+#[test]
+fn cli_fmt() {
+    let output = run_jjay(&["fmt"], "let x=1;{a:x}");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "let x = 1;\n{ a: x }\n"
+    );
+
+    assert!(run_jjay(&["fmt", "--check"], "let x = 1;\n{ a: x }\n")
+        .status
+        .success());
+    assert!(!run_jjay(&["fmt", "--check"], "let x=1;\n{ a: x }\n")
+        .status
+        .success());
+    assert_error(&["fmt"], "{ a: }", "expected");
+
+    let dir = std::env::temp_dir().join(format!("jjay-cli-fmt-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("script.jjay");
+    std::fs::write(&path, "[1,2]").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = run_jjay(&["fmt", "--check", path], "");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("is not formatted"));
+    assert!(run_jjay(&["fmt", path], "").status.success());
+    assert_eq!(std::fs::read_to_string(path).unwrap(), "[1, 2]\n");
+    assert!(run_jjay(&["fmt", "--check", path], "").status.success());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_check() {
+    let output = run_jjay(&["check"], "let x = 1;\n{ a: y }");
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "-:1:5: warning[unused-variable]: Unused variable: x\n\
+         -:2:6: error[undefined-variable]: Variable not found: y\n"
+    );
+
+    let output = run_jjay(&["check", "--json", "-D", "input"], "let x = 1;\ninput");
+    assert!(output.status.success());
+    let diagnostics: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        diagnostics,
+        serde_json::json!([{
+            "file": "-",
+            "severity": "warning",
+            "code": "unused-variable",
+            "message": "Unused variable: x",
+            "line": 1,
+            "column": 5,
+            "end_line": 1,
+            "end_column": 6,
+        }])
+    );
+}
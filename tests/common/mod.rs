@@ -56,7 +56,6 @@ macro_rules! make_json_test {
 }
 
 #[allow(unused)]
-
 pub fn run_json_test(data: &[u8], expected: Option<bool>) {
     let source = String::from_utf8(data.to_vec()).unwrap();
     if expected.unwrap_or(true) {
@@ -104,7 +103,7 @@ pub fn run_script_test(source: &str, expected: &str) {
     }
 }
 pub fn run_script_parsefail_test(source: &str) {
-    match run_test(&source) {
+    match run_test(source) {
         Ok(_) => panic!("expected failure"),
         Err(ScriptError::Parse(_)) => (/* OK */),
         Err(err) => panic!("test failure:\n{}", err),
@@ -112,7 +111,7 @@ pub fn run_script_parsefail_test(source: &str) {
 }
 
 pub fn run_script_fail_test(source: &str) {
-    match run_test(&source) {
+    match run_test(source) {
         Ok(_) => panic!("expected failure"),
         Err(ScriptError::Parse(err)) => panic!("parse error:\n{}", err),
         Err(_) => (/* OK */),
@@ -141,7 +140,7 @@ fn compare_json_value(actual: &serde_json::Value, expected: &serde_json::Value)
                 }
             }
 
-            return true;
+            true
         }
 
         (Value::Array(actual_items), Value::Array(expected_items)) => {
@@ -157,7 +156,7 @@ fn compare_json_value(actual: &serde_json::Value, expected: &serde_json::Value)
         }
 
         (Value::Number(actual_value), Value::Number(expected_value)) => {
-            let actual_value = match expected_value.as_f64() {
+            let actual_value = match actual_value.as_f64() {
                 Some(value) => value,
                 None => return false,
             };
@@ -166,11 +165,11 @@ fn compare_json_value(actual: &serde_json::Value, expected: &serde_json::Value)
                 None => return false,
             };
 
-            return float_cmp::ApproxEq::approx_eq(
+            float_cmp::ApproxEq::approx_eq(
                 actual_value,
                 expected_value,
                 float_cmp::F64Margin::zero(),
-            );
+            )
         }
 
         (Value::Bool(actual_value), Value::Bool(expected_value)) => actual_value == expected_value,
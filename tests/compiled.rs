@@ -0,0 +1,63 @@
+use std::thread;
+
+use jjay::*;
+
+#[test]
+fn compiled_run_many() {
+    let script = CompiledScript::new("let double(x) = x * 2; { port: double(input) }").unwrap();
+    for i in 0..3 {
+        let scope = Scope::new_default().set_nofail("input", Value::Number(i as f64));
+        let value = script.run(scope).unwrap();
+        assert_eq!(
+            value.to_json().unwrap(),
+            serde_json::json!({ "port": i as f64 * 2.0 })
+        );
+    }
+}
+
+#[test]
+fn compiled_errors() {
+    assert!(matches!(
+        CompiledScript::new("let x = ;"),
+        Err(ScriptError::Parse(..))
+    ));
+
+    // errors of running the script are returned by every run
+    let script = CompiledScript::new("input.name").unwrap();
+    let scope = Scope::new_default().set_nofail("input", Value::Null);
+    assert!(script.run(scope.clone()).is_err());
+    assert!(script.run(scope).is_err());
+}
+
+#[test]
+fn compiled_checked() {
+    let scope = Scope::new_default().set_nofail("input", Value::Null);
+    assert!(CompiledScript::new_checked("input.name", &scope).is_ok());
+
+    let err = CompiledScript::new_checked("let x = 1; input.name + y", &scope).unwrap_err();
+    assert_eq!(err.span().map(|span| span.column), Some(25));
+    assert_eq!(err.root_cause().to_string(), "Variable not found: y");
+
+    // warnings are not errors
+    assert!(CompiledScript::new_checked("let unused = 1; input", &scope).is_ok());
+}
+
+#[test]
+fn compiled_threads() {
+    let script = CompiledScript::new("[input, input + 1]").unwrap();
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let script = script.clone();
+            thread::spawn(move || {
+                let scope = Scope::new_default().set_nofail("input", Value::Number(i as f64));
+                script.run(scope).unwrap().to_json().unwrap()
+            })
+        })
+        .collect();
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(
+            handle.join().unwrap(),
+            serde_json::json!([i as f64, i as f64 + 1.0])
+        );
+    }
+}
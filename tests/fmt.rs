@@ -0,0 +1,172 @@
+use std::path::Path;
+
+use jjay::*;
+
+fn format(source: &str, width: usize) -> String {
+    jjay::format_script(source, FormatOptions { width })
+        .unwrap_or_else(|err| panic!("test failure:\n{}", err.render(Some(source))))
+}
+
+/// Format a script and check that the result is stable and evaluates to the same value.
+fn assert_formatted(source: &str, width: usize, expected: &str) {
+    let formatted = format(source, width);
+    assert_eq!(formatted, expected);
+    assert_eq!(format(&formatted, width), formatted);
+    assert_eq!(
+        jjay::run_script(source)
+            .and_then(|value| value.to_json())
+            .ok(),
+        jjay::run_script(&formatted)
+            .and_then(|value| value.to_json())
+            .ok()
+    );
+}
+
+#[test]
+fn fmt_spacing() {
+    assert_formatted(
+        "let f(x)( y:number )=x+y*2;f(1)(2)",
+        100,
+        "let f(x)(y: number) = x + y * 2;\nf(1)(2)\n",
+    );
+    assert_formatted(
+        "{a:[1,2,],\"b c\":(x=>x)(1),d:null?.e?[\"f\"]}",
+        100,
+        "{ a: [1, 2], \"b c\": (x => x)(1), d: null?.e?[\"f\"] }\n",
+    );
+    assert_formatted(
+        "let x=1;\n\n\nlet y=(let z=2; z);`${ x+y }`",
+        100,
+        "let x = 1;\n\nlet y = (\n  let z = 2;\n  z\n);\n`${x + y}`\n",
+    );
+    assert_formatted(
+        "match [1] case [a,...rest] then a case {k:v,...} if v>0 then v case _ then 0 end",
+        100,
+        "match [1]\ncase [a, ...rest] then a\ncase { k: v, ... } if v > 0 then v\ncase _ then 0\nend\n",
+    );
+}
+
+#[test]
+fn fmt_line_breaking() {
+    let source = "{ name: \"web\", ports: [80, 443], check: if true then \"yes\" else \"no\" end }";
+    assert_formatted(source, 100, &format!("{}\n", source));
+    assert_formatted(
+        source,
+        40,
+        r#"{
+  name: "web",
+  ports: [80, 443],
+  check: if true then
+    "yes"
+  else
+    "no"
+  end,
+}
+"#,
+    );
+    assert_formatted(
+        "[1, 2, 3] | map((x => x * 2)) | filter((x => x > 2))",
+        30,
+        "[1, 2, 3]\n  | map((x => x * 2))\n  | filter((x => x > 2))\n",
+    );
+}
+
+#[test]
+fn fmt_comments() {
+    assert_formatted(
+        r#"// header
+let x = 1; // one
+/* block
+   comment */
+let y = [ // items
+  x,
+
+  2, /* two */
+  // end
+];
+y /* done */"#,
+        100,
+        r#"// header
+let x = 1; // one
+/* block
+   comment */
+let y = [
+  // items
+  x,
+
+  2, /* two */
+  // end
+];
+y /* done */
+"#,
+    );
+    assert_formatted(
+        "{ a: 1, // first\n b: \"//\" }",
+        100,
+        "{\n  a: 1, // first\n  b: \"//\",\n}\n",
+    );
+    assert_formatted(
+        "[1] // first\n  // filter\n  | filter((x => x > 0))",
+        100,
+        "[1] // first\n  // filter\n  | filter((x => x > 0))\n",
+    );
+}
+
+#[test]
+fn fmt_comments_in_expressions() {
+    assert_formatted(
+        "let f(x)=x;let x=/* one */1;f(/* arg */x)",
+        100,
+        "let f(x) = x;\nlet x = /* one */ 1;\nf(/* arg */ x)\n",
+    );
+    assert_formatted(
+        "if /* cond */ true then `${ /* x */ 1 }` else 2 end",
+        100,
+        "if /* cond */ true then `${/* x */ 1}` else 2 end\n",
+    );
+    assert_formatted(
+        "(let x = 1;\n  x // value\n  // end\n)",
+        100,
+        "(\n  let x = 1;\n  x // value\n  // end\n)\n",
+    );
+}
+
+#[test]
+fn fmt_errors() {
+    assert!(jjay::format_script("let x = ;", FormatOptions::default()).is_err());
+
+    // comments that can't be written where they are
+    for source in &[
+        "f(1 /* arg */)",
+        "f( // arg\n1)",
+        "let /* name */ x = 1; x",
+        "if true then 1 /* then */ else 2 end",
+        "match 1 case /* one */ 1 then 1 case _ then 2 end",
+        "import /* path */ \"a.jjay\"",
+    ] {
+        let err = jjay::format_script(source, FormatOptions::default()).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Cannot format a comment in this position",
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn fmt_test_scripts() {
+    fn visit(dir: &Path) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                visit(&path);
+            } else if path.extension().is_some_and(|ext| ext == "jjay") {
+                let source = std::fs::read_to_string(&path).unwrap();
+                let formatted = format(&source, 100);
+                assert_eq!(format(&formatted, 100), formatted, "{}", path.display());
+            }
+        }
+    }
+    visit(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests"));
+}
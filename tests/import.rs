@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+
+use jjay::*;
+
+fn data_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/import_data")
+        .join(name)
+}
+
+fn run_import_test(importer: &Importer, name: &str, expected: &str) {
+    let value = importer
+        .run_file(data_path(name), Scope::new_default())
+        .and_then(|value| value.to_json())
+        .unwrap_or_else(|err| panic!("test failure:\n{}", err));
+    let expected: serde_json::Value = serde_json::from_str(expected).unwrap();
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn import_relative() {
+    run_import_test(
+        &Importer::new(),
+        "main.jjay",
+        r#"{"replicas": 2.0, "region": "us", "debug": true, "motd": "hello\n"}"#,
+    );
+}
+
+#[test]
+fn import_search_path() {
+    let importer = Importer::new().with_search_path(data_path("lib"));
+    run_import_test(&importer, "search_path.jjay", "42.0");
+}
+
+#[test]
+fn import_search_path_missing() {
+    let err = Importer::new()
+        .run_file(data_path("search_path.jjay"), Scope::new_default())
+        .unwrap_err();
+    match err.root_cause() {
+        ScriptError::ImportNotFound(path) => assert_eq!(path, "util.jjay"),
+        err => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn import_not_found() {
+    assert!(jjay::run_file(data_path("missing.jjay")).is_err());
+}
+
+#[test]
+fn import_cycle() {
+    let err = jjay::run_file(data_path("cycle/a.jjay")).unwrap_err();
+    match err.root_cause() {
+        ScriptError::ImportCycle(paths) => {
+            let names: Vec<_> = paths
+                .iter()
+                .map(|path| path.file_name().unwrap().to_str().unwrap())
+                .collect();
+            assert_eq!(names, ["a.jjay", "b.jjay", "a.jjay"]);
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn import_cached() {
+    let dir = std::env::temp_dir().join(format!("jjay-import-cached-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("main.jjay"), r#"import_str "value.txt""#).unwrap();
+    std::fs::write(dir.join("value.txt"), "old").unwrap();
+
+    let importer = Importer::new();
+    let run = |importer: &Importer| {
+        importer
+            .run_file(dir.join("main.jjay"), Scope::new_default())
+            .and_then(|value| value.to_json())
+            .unwrap()
+    };
+
+    assert_eq!(run(&importer), "old");
+    std::fs::write(dir.join("value.txt"), "new").unwrap();
+    assert_eq!(run(&importer), "old");
+    assert_eq!(run(&Importer::new()), "new");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn read_data_files() {
+    run_import_test(
+        &Importer::new(),
+        "read.jjay",
+        r#"{
+            "yaml": {"name": "web", "ports": [80.0, 443.0], "debug": "no"},
+            "toml": {"name": "web", "released": "2024-01-02", "limits": {"cpu": 2.0, "memory": "512Mi"}},
+            "csv": [{"name": "web", "address": "10.0.0.1"}, {"name": "db", "address": "10.0.0.2"}]
+        }"#,
+    );
+}
+
+#[test]
+fn filesystem_disabled() {
+    let importer = Importer::new().with_filesystem_access(false);
+    for name in &["read.jjay", "main.jjay"] {
+        let err = importer
+            .run_file(data_path(name), Scope::new_default())
+            .unwrap_err();
+        match err.root_cause() {
+            ScriptError::FilesystemDisabled(_) => (),
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+}
+
+#[test]
+fn import_default_scope() {
+    // the default scope imports files relative to the current directory
+    let value = run_script_with(
+        r#"[import_str "tests/import_data/motd.txt", read_yaml("tests/import_data/motd.txt")]"#,
+        Scope::new_default(),
+    )
+    .unwrap();
+    assert_eq!(
+        value.to_json().unwrap(),
+        serde_json::json!(["hello\n", "hello"])
+    );
+}
@@ -0,0 +1,33 @@
+use jjay::*;
+
+fn to_ini(source: &str) -> ScriptResult<String> {
+    jjay::run_script(source).and_then(|value| value.to_ini())
+}
+
+#[test]
+fn ini_sections() {
+    assert_eq!(
+        to_ini(
+            r#"{ db: { host: "h", port: 5432, debug: false }, name: "x", web: { ratio: 0.5 } }"#
+        )
+        .unwrap(),
+        "name = x\n\n[db]\ndebug = false\nhost = h\nport = 5432\n\n[web]\nratio = 0.5\n"
+    );
+}
+
+#[test]
+fn ini_errors() {
+    let message = |source| to_ini(source).unwrap_err().to_string();
+    assert_eq!(
+        message("{ db: { pool: { size: 1 } } }"),
+        "Cannot write INI at db.pool: only one level of sections is supported"
+    );
+    assert_eq!(
+        message("{ db: { hosts: [] } }"),
+        "Cannot write INI at db.hosts: arrays are not supported"
+    );
+    assert_eq!(
+        message("{ motd: \"a\\nb\" }"),
+        "Cannot write INI at motd: strings with several lines are not supported"
+    );
+}
@@ -0,0 +1,226 @@
+#![cfg(feature = "cli")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Command, Stdio};
+
+use serde_json::{json, Value};
+
+const URI: &str = "file:///tmp/lsp_test.jjay";
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn position(line: u32, character: u32) -> Value {
+    json!({
+        "textDocument": { "uri": URI },
+        "position": { "line": line, "character": character },
+    })
+}
+
+/// Send messages to the language server and return the messages it sent back, in order.
+fn run_session(messages: &[Value]) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_jjay-lsp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    for message in messages {
+        let body = message.to_string();
+        write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    }
+    drop(stdin);
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut responses = Vec::new();
+    'messages: loop {
+        let mut length = None;
+        loop {
+            let mut header = String::new();
+            if stdout.read_line(&mut header).unwrap() == 0 {
+                break 'messages;
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length: ") {
+                length = Some(value.parse().unwrap());
+            }
+        }
+        let mut body = vec![0; length.unwrap()];
+        stdout.read_exact(&mut body).unwrap();
+        responses.push(serde_json::from_slice(&body).unwrap());
+    }
+
+    assert!(child.wait().unwrap().success());
+    responses
+}
+
+fn session(text: &str, requests: &[(&str, Value)]) -> (Vec<Value>, Vec<Value>) {
+    let mut messages = vec![
+        request(0, "initialize", json!({ "capabilities": {} })),
+        notification("initialized", json!({})),
+        notification(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": URI, "languageId": "jjay", "version": 1, "text": text },
+            }),
+        ),
+    ];
+    for (i, (method, params)) in requests.iter().enumerate() {
+        messages.push(request(i as u64 + 1, method, params.clone()));
+    }
+    messages.push(request(1000, "shutdown", Value::Null));
+    messages.push(notification("exit", Value::Null));
+
+    let messages = run_session(&messages);
+    let diagnostics = messages
+        .iter()
+        .filter(|m| m["method"] == "textDocument/publishDiagnostics")
+        .map(|m| m["params"]["diagnostics"].clone())
+        .collect();
+    let results = (1..=requests.len() as u64)
+        .map(|id| {
+            let response = messages.iter().find(|m| m["id"] == id).unwrap();
+            assert!(response["error"].is_null(), "error: {}", response);
+            response["result"].clone()
+        })
+        .collect();
+    (diagnostics, results)
+}
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Value {
+    json!({
+        "start": { "line": start.0, "character": start.1 },
+        "end": { "line": end.0, "character": end.1 },
+    })
+}
+
+#[test]
+fn lsp_features() {
+    let text =
+        "let port = 8000 + 80;\nlet url(host) = `http://${host}:${port}`;\n{ api: url(\"api\") }\n";
+    let (diagnostics, results) = session(
+        text,
+        &[
+            ("textDocument/definition", position(1, 34)),
+            ("textDocument/definition", position(1, 27)),
+            ("textDocument/hover", position(0, 5)),
+            ("textDocument/hover", position(2, 7)),
+            ("textDocument/completion", position(1, 30)),
+            (
+                "textDocument/documentSymbol",
+                json!({ "textDocument": { "uri": URI } }),
+            ),
+        ],
+    );
+
+    assert_eq!(diagnostics, vec![json!([])]);
+
+    assert_eq!(
+        results[0],
+        json!({ "uri": URI, "range": range((0, 4), (0, 8)) })
+    );
+    assert_eq!(
+        results[1],
+        json!({ "uri": URI, "range": range((1, 8), (1, 12)) })
+    );
+
+    assert_eq!(results[2]["contents"]["value"], "```\nport = 8080.0\n```");
+    assert_eq!(results[2]["range"], range((0, 4), (0, 8)));
+    assert_eq!(
+        results[3]["contents"]["value"],
+        "```\nfunction url(host)\n```"
+    );
+
+    let labels: Vec<&str> = results[4]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert_eq!(&labels[..2], ["host", "port"]);
+    assert!(labels.contains(&"map"));
+    assert!(!labels.contains(&"url"));
+
+    let symbols: Vec<(&str, u64)> = results[5]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| (s["name"].as_str().unwrap(), s["kind"].as_u64().unwrap()))
+        .collect();
+    // 13 is a variable and 12 is a function
+    assert_eq!(symbols, [("port", 13), ("url", 12)]);
+}
+
+#[test]
+fn lsp_diagnostics() {
+    let (diagnostics, _) = session("let x = 1;\nlet y = [1] + x;\n{ y: y, z: z }", &[]);
+    assert_eq!(
+        diagnostics,
+        vec![json!([{
+            "range": range((2, 11), (2, 12)),
+            "severity": 1,
+            "code": "undefined-variable",
+            "source": "jjay",
+            "message": "Variable not found: z",
+        }])]
+    );
+
+    let (diagnostics, _) = session("let x = 1;\nlet y = [1] + x;\n{ y: y }", &[]);
+    assert_eq!(diagnostics[0][0]["code"], "runtime-error");
+    assert_eq!(diagnostics[0][0]["range"]["start"]["line"], 1);
+
+    let (diagnostics, _) = session("let x = ;", &[]);
+    assert_eq!(diagnostics[0][0]["code"], "syntax-error");
+}
+
+#[test]
+fn lsp_no_evaluation() {
+    // scripts are not run while editing, because they may not terminate or may read files
+    let text =
+        "let loop(f) = f(f);\nlet x = loop(loop);\nlet y = import_str \"/etc/hostname\";\n[x, y]\n";
+    let (diagnostics, results) = session(
+        text,
+        &[
+            ("textDocument/hover", position(1, 4)),
+            ("textDocument/hover", position(2, 4)),
+        ],
+    );
+    assert_eq!(diagnostics, vec![json!([])]);
+    assert_eq!(results[0]["contents"]["value"], "```\nvariable x\n```");
+    assert_eq!(results[1]["contents"]["value"], "```\nvariable y\n```");
+}
+
+#[test]
+fn lsp_formatting() {
+    let options = json!({
+        "textDocument": { "uri": URI },
+        "options": { "tabSize": 2, "insertSpaces": true },
+    });
+
+    let (_, results) = session(
+        "let x=[1,\n2];\nx",
+        &[("textDocument/formatting", options.clone())],
+    );
+    assert_eq!(
+        results[0],
+        json!([{
+            "range": range((0, 0), (2, 1)),
+            "newText": "let x = [1, 2];\nx\n",
+        }])
+    );
+
+    // documents that are already formatted or can't be parsed are not changed
+    let (_, results) = session("1\n", &[("textDocument/formatting", options.clone())]);
+    assert_eq!(results[0], json!([]));
+    let (_, results) = session("let x = ;", &[("textDocument/formatting", options)]);
+    assert_eq!(results[0], Value::Null);
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use serde_json::json;
+
+use jjay::*;
+
+fn scope() -> Scope {
+    Scope::new_default()
+        .register("url", &["host", "port"], |host: String, port: f64| {
+            format!("http://{}:{}", host, port)
+        })
+        .register(
+            "clamp",
+            &["min", "max", "x"],
+            |min: f64, max: f64, x: f64| x.max(min).min(max),
+        )
+        .register("total", &["xs"], |xs: Vec<f64>| xs.iter().sum::<f64>())
+        .register("negate", &["flag"], |flag: bool| !flag)
+        .register(
+            "labels",
+            &["labels"],
+            |labels: BTreeMap<String, String>| -> Vec<String> {
+                labels
+                    .into_iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect()
+            },
+        )
+        .register("or_default", &["x"], |x: Option<String>| {
+            x.unwrap_or_else(|| "default".to_string())
+        })
+        .register("describe", &["x"], |x: Value| x.value_type().to_string())
+        .register("sqrt", &["x"], |x: f64| {
+            if x < 0.0 {
+                return Err(ScriptError::Other("sqrt: negative number".to_string()));
+            }
+            Ok(x.sqrt())
+        })
+}
+
+fn run(source: &str) -> ScriptResult<serde_json::Value> {
+    run_script_with(source, scope())?.to_json()
+}
+
+#[test]
+fn native_conversions() {
+    assert_eq!(run(r#"url("db")(5432)"#).unwrap(), json!("http://db:5432"));
+    assert_eq!(run("total([1, 2, 3.5])").unwrap(), json!(6.5));
+    assert_eq!(run("negate(false)").unwrap(), json!(true));
+    assert_eq!(
+        run(r#"labels({ app: "web", tier: "db" })"#).unwrap(),
+        json!(["app=web", "tier=db"])
+    );
+    assert_eq!(
+        run(r#"[or_default(null), or_default("x")]"#).unwrap(),
+        json!(["default", "x"])
+    );
+    assert_eq!(run("or_default()").unwrap(), json!("default"));
+    assert_eq!(run("describe([1])").unwrap(), json!("Array"));
+}
+
+#[test]
+fn native_currying() {
+    assert_eq!(
+        run("let f = clamp(0)(10); [f(-5), f(5), 15 | f]").unwrap(),
+        json!([0.0, 5.0, 10.0])
+    );
+    assert_eq!(run(r#"5432 | url("db")"#).unwrap(), json!("http://db:5432"));
+}
+
+#[test]
+fn native_errors() {
+    let err = run(r#"url("db")("5432")"#).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Invalid argument port to function url: expected Number, found String"
+    );
+
+    let err = run(r#"total([1, "2"])"#).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Invalid argument xs to function total: expected [Number], found Array"
+    );
+
+    let err = run("labels({ a: 1 })").unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Invalid argument labels to function labels: expected Object of String, found Object"
+    );
+
+    let err = run("or_default(1)").unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Invalid argument x to function or_default: expected String or Null, found Number"
+    );
+
+    // errors returned by the function
+    let err = run("sqrt(-1)").unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "sqrt: negative number");
+    assert_eq!(run("sqrt(4)").unwrap(), json!(2.0));
+}
+
+#[test]
+#[should_panic(expected = "function f has 2 parameters, but 1 names were given")]
+fn native_param_names() {
+    Function::from_fn("f", &["x"], |x: f64, y: f64| x + y);
+}
+
+#[test]
+fn native_into_value() {
+    let scope = Scope::new_default()
+        .set_nofail("port", 8080.0)
+        .set_nofail("hosts", vec!["web", "db"])
+        .set_nofail("debug", Some(false))
+        .set_nofail("extra", None::<String>);
+    assert_eq!(
+        run_script_with("[port, hosts, debug, extra]", scope)
+            .unwrap()
+            .to_json()
+            .unwrap(),
+        json!([8080.0, ["web", "db"], false, null])
+    );
+}
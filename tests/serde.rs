@@ -0,0 +1,267 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use jjay::*;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Config {
+    name: String,
+    spec: Spec,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Spec {
+    replicas: u32,
+    ports: Vec<Port>,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+    timeout: Option<f64>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Port {
+    port: u16,
+    protocol: Protocol,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Protocol {
+    Tcp,
+    Udp,
+    Other(String),
+}
+
+fn render<T: serde::de::DeserializeOwned>(source: &str) -> ScriptResult<T> {
+    from_value(run_script(source)?)
+}
+
+#[test]
+fn serde_from_value() {
+    let config: Config = render(
+        r#"
+        let port(n) = { port: n, protocol: "tcp" };
+        {
+          name: "web",
+          spec: { replicas: 3, ports: [port(80), port(443), { port: 53, protocol: { other: "dns" } }], timeout: null },
+        }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        config,
+        Config {
+            name: "web".to_string(),
+            spec: Spec {
+                replicas: 3,
+                ports: vec![
+                    Port {
+                        port: 80,
+                        protocol: Protocol::Tcp
+                    },
+                    Port {
+                        port: 443,
+                        protocol: Protocol::Tcp
+                    },
+                    Port {
+                        port: 53,
+                        protocol: Protocol::Other("dns".to_string())
+                    },
+                ],
+                labels: BTreeMap::new(),
+                timeout: None,
+            },
+        }
+    );
+
+    let tuple: (f64, bool, Option<String>) = render(r#"[1.5, true, "x"]"#).unwrap();
+    assert_eq!(tuple, (1.5, true, Some("x".to_string())));
+}
+
+#[test]
+fn serde_error_paths() {
+    fn error(source: &str) -> String {
+        render::<Config>(source).unwrap_err().to_string()
+    }
+
+    let spec = r#"{ name: "web", spec: { replicas: 1, ports: [{ port: 80, protocol: "tcp" }, { port: 81, protocol: "tcp" }, PORT] } }"#;
+    assert_eq!(
+        error(&spec.replace("PORT", r#"{ port: "http", protocol: "tcp" }"#)),
+        "spec.ports[2].port: expected integer, found String"
+    );
+    assert_eq!(
+        error(&spec.replace("PORT", r#"{ port: 80.5, protocol: "tcp" }"#)),
+        "spec.ports[2].port: expected integer, found 80.5"
+    );
+    assert_eq!(
+        error(&spec.replace("PORT", r#"{ port: 70000, protocol: "tcp" }"#)),
+        "spec.ports[2].port: expected integer between 0 and 65535, found 70000"
+    );
+    assert_eq!(
+        error(&spec.replace("PORT", r#"{ port: 80 }"#)),
+        "spec.ports[2]: missing field `protocol`"
+    );
+    assert_eq!(
+        error(&spec.replace("PORT", r#"{ port: 80, protocol: "sctp" }"#)),
+        "spec.ports[2].protocol: unknown variant `sctp`, expected one of `tcp`, `udp`, `other`"
+    );
+    assert_eq!(
+        error(&spec.replace("PORT", r#"{ port: 80, protocol: { other: 1 } }"#)),
+        "spec.ports[2].protocol.other: expected string, found Number"
+    );
+    assert_eq!(
+        error(r#"{ name: "web", spec: { replicas: 1, ports: [], labels: { app: null } } }"#),
+        "spec.labels.app: expected string, found Null"
+    );
+    assert_eq!(error("[]"), "expected object, found Array");
+
+    // numbers too large for 128-bit integers are not saturated
+    assert!(from_value::<i128>(Value::Number(1e40)).is_err());
+    assert!(from_value::<u128>(Value::Number(1e40)).is_err());
+    assert!(from_value::<i128>(Value::Number(-1e40)).is_err());
+    assert_eq!(
+        from_value::<i128>(Value::Number(-1e20)).unwrap(),
+        -100_000_000_000_000_000_000
+    );
+    assert_eq!(
+        from_value::<u128>(Value::Number(1e20)).unwrap(),
+        100_000_000_000_000_000_000
+    );
+}
+
+#[test]
+fn serde_to_value() {
+    let config = Config {
+        name: "web".to_string(),
+        spec: Spec {
+            replicas: 2,
+            ports: vec![
+                Port {
+                    port: 80,
+                    protocol: Protocol::Udp,
+                },
+                Port {
+                    port: 53,
+                    protocol: Protocol::Other("dns".to_string()),
+                },
+            ],
+            labels: vec![("app".to_string(), "web".to_string())]
+                .into_iter()
+                .collect(),
+            timeout: Some(1.5),
+        },
+    };
+    let value = to_value(&config).unwrap();
+    assert_eq!(
+        value.to_json().unwrap(),
+        json!({
+            "name": "web",
+            "spec": {
+                "replicas": 2.0,
+                "ports": [
+                    { "port": 80.0, "protocol": "udp" },
+                    { "port": 53.0, "protocol": { "other": "dns" } },
+                ],
+                "labels": { "app": "web" },
+                "timeout": 1.5,
+            },
+        })
+    );
+
+    // values converted from Rust can be used by scripts, and converted back
+    let scope = Scope::new_default().set_nofail("config", value);
+    let value = run_script_with("config.spec.ports[1].protocol.other", scope).unwrap();
+    assert_eq!(from_value::<String>(value).unwrap(), "dns");
+    assert_eq!(
+        from_value::<Config>(to_value(&config).unwrap()).unwrap(),
+        config
+    );
+
+    let err = to_value(&vec![1u64, 1 << 60]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[1]: integer 1152921504606846976 is too large to be represented exactly"
+    );
+}
+
+#[test]
+fn serde_value() {
+    // integers are serialized without a fractional part
+    let value = run_script(r#"{ port: 8080, ratio: 0.5, tags: ["a"], none: null }"#).unwrap();
+    assert_eq!(
+        serde_json::to_string(&value).unwrap(),
+        r#"{"none":null,"port":8080,"ratio":0.5,"tags":["a"]}"#
+    );
+    let value = run_script(r#"{ port: 8080, ratio: 0.5 }"#).unwrap();
+    assert_eq!(
+        toml::to_string(&value).unwrap(),
+        "port = 8080\nratio = 0.5\n"
+    );
+
+    let value: Value = serde_json::from_str(r#"{"a": [1, 2.5, "x", true, null]}"#).unwrap();
+    assert_eq!(
+        value.to_json().unwrap(),
+        json!({ "a": [1.0, 2.5, "x", true, null] })
+    );
+
+    // functions can't be serialized
+    let value = run_script("{ f: (x => x) }").unwrap();
+    assert_eq!(
+        serde_json::to_string(&value).unwrap_err().to_string(),
+        "cannot serialize a function"
+    );
+    assert_eq!(
+        from_value::<Value>(value).unwrap_err().to_string(),
+        "f: cannot convert a function"
+    );
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Flattened {
+    name: String,
+    #[serde(flatten)]
+    size: Size,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Size {
+    bytes: u64,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum Limit {
+    Bytes(u64),
+    Name(String),
+}
+
+#[test]
+fn serde_large_integers() {
+    // integers up to 2^53 are exact, so they are deserialized as integers even when buffered
+    let value: Flattened = render(r#"{ name: "disk", bytes: 2000000000000000 }"#).unwrap();
+    assert_eq!(
+        value,
+        Flattened {
+            name: "disk".to_string(),
+            size: Size {
+                bytes: 2000000000000000
+            },
+        }
+    );
+    let value: Vec<Limit> = render(r#"[9007199254740992, "max"]"#).unwrap();
+    assert_eq!(
+        value,
+        vec![
+            Limit::Bytes(9007199254740992),
+            Limit::Name("max".to_string())
+        ]
+    );
+
+    let value = run_script("[2000000000000000, 9007199254740992]").unwrap();
+    assert_eq!(
+        serde_json::to_string(&value).unwrap(),
+        "[2000000000000000,9007199254740992]"
+    );
+}
@@ -0,0 +1,78 @@
+#[macro_use]
+mod common;
+
+make_test!(map_array: "[1, 2, 3] | map((x => x * 2))" => "[2, 4, 6]");
+make_test!(map_object: "{ a: 1, b: 2 } | map((x => x + 1))" => r#"{"a": 2, "b": 3}"#);
+make_fail_test!(map_not_collection: "3 | map((x => x))");
+make_test!(filter_array: "[1, 2, 3, 4] | filter((x => x > 2))" => "[3, 4]");
+make_test!(filter_object: "{ a: 1, b: 2 } | filter((x => x > 1))" => r#"{"b": 2}"#);
+make_fail_test!(filter_not_bool: "[1, 2] | filter((x => x))");
+make_test!(fold: "[1, 2, 3] | fold((acc => x => acc + x))(10)" => "16");
+make_test!(reduce: "[1, 2, 3] | reduce((acc => x => acc * x))" => "6");
+make_fail_test!(reduce_empty: "[] | reduce((acc => x => acc + x))");
+make_test!(flat_map: "[1, 2] | flat_map((x => [x, x * 10]))" => "[1, 10, 2, 20]");
+make_test!(keys_values: "let o = { b: 2, a: 1 }; [keys(o), values(o)]" => r#"[["a", "b"], [1, 2]]"#);
+make_test!(entries: "entries({ a: 1, b: 2 })" => r#"[["a", 1], ["b", 2]]"#);
+make_test!(from_entries: r#"from_entries([["a", 1], ["b", 2]])"# => r#"{"a": 1, "b": 2}"#);
+make_fail_test!(from_entries_not_pair: r#"from_entries([["a"]])"#);
+make_test!(len: r#"[len([1, 2]), len({ a: 1 }), len("héllo")]"# => "[2, 1, 5]");
+make_fail_test!(len_number: "len(3)");
+make_test!(sort: "sort([3, 1, 2])" => "[1, 2, 3]");
+make_test!(sort_strings: r#"sort(["b", "c", "a"])"# => r#"["a", "b", "c"]"#);
+make_fail_test!(sort_mixed: r#"sort([1, "a"])"#);
+make_fail_test!(sort_mixed_many: r#"sort([3, 1, "a", 2, 5, 4, 7, 6, 9, 8, 11, 10, 13, 12, 15, 14, 17, 16, 19, 18, 21, 20])"#);
+make_fail_test!(sort_nan: "sort([1, 0 / 0, 2])");
+make_fail_test!(sort_objects: "sort([{ a: 1 }, { a: 2 }])");
+make_test!(sort_arrays: "sort([[2, 1], [1], [1, 0], []])" => "[[], [1], [1, 0], [2, 1]]");
+make_fail_test!(sort_arrays_mixed: r#"sort([[1, 2], [1, "a"], [1]])"#);
+make_test!(sort_by: r#"[{ n: 2 }, { n: 1 }] | sort_by((x => x.n))"# => r#"[{"n": 1}, {"n": 2}]"#);
+make_test!(unique: "unique([1, 2, 1, [3], [3]])" => "[1, 2, [3]]");
+make_test!(group_by: r#"[{ k: "a", v: 1 }, { k: "b", v: 2 }, { k: "a", v: 3 }] | group_by((x => x.k)) | map(map((x => x.v)))"# => r#"{"a": [1, 3], "b": [2]}"#);
+make_test!(zip: r#"zip([1, 2, 3])(["a", "b"])"# => r#"[[1, "a"], [2, "b"]]"#);
+make_test!(range: "range(2)(5)" => "[2, 3, 4]");
+make_test!(range_empty: "range(5)(2)" => "[]");
+make_fail_test!(range_fraction: "range(0)(2.5)");
+make_fail_test!(range_infinite: "range(0)(1 / 0)");
+make_fail_test!(range_too_long: "range(0)(1e12)");
+make_test!(any_all: "let xs = [1, 2, 3]; [any((x => x > 2))(xs), all((x => x > 2))(xs), any((x => x > 2))([]), all((x => x > 2))([])]" => "[true, false, false, true]");
+make_test!(find: "[[1, 2, 3] | find((x => x > 1)), [1] | find((x => x > 1))]" => "[2, null]");
+make_test!(index_of: r#"[[1, "a", 2] | index_of("a"), [1] | index_of(2)]"# => "[1, null]");
+make_test!(reverse: "reverse([1, 2, 3])" => "[3, 2, 1]");
+make_test!(take_drop: "let xs = [1, 2, 3]; [take(2)(xs), drop(2)(xs), take(5)(xs), drop(5)(xs)]" => "[[1, 2], [3], [1, 2, 3], []]");
+make_test!(pipeline: "range(0)(10) | filter((x => x > 4)) | map((x => x * x)) | take(2)" => "[25, 36]");
+make_test!(add_strings: r#""foo" + "bar""# => r#""foobar""#);
+make_fail_test!(add_string_number: r#""foo" + 1"#);
+make_test!(split: r#""a,b,,c" | split(",")"# => r#"["a", "b", "", "c"]"#);
+make_fail_test!(split_empty_separator: r#""abc" | split("")"#);
+make_test!(join: r#"["a", 1, true] | join("-")"# => r#""a-1-true""#);
+make_test!(trim: r#"trim("  a b  ")"# => r#""a b""#);
+make_test!(upper_lower: r#"[upper("aBc"), lower("aBc")]"# => r#"["ABC", "abc"]"#);
+make_test!(replace: r#""a.b.c" | replace(".")("-")"# => r#""a-b-c""#);
+make_test!(starts_ends_with: r#"["https://x" | starts_with("https://"), "x.json" | ends_with(".yaml")]"# => "[true, false]");
+make_test!(contains: r#"["hostname" | contains("name"), [1, 2] | contains(3)]"# => "[true, false]");
+make_test!(substring: r#""héllo wörld" | substring(1)(8)"# => r#""éllo wö""#);
+make_test!(substring_out_of_range: r#"["abc" | substring(2)(10), "abc" | substring(5)(10), "abc" | substring(2)(1)]"# => r#"["c", "", ""]"#);
+make_test!(pad: r#"[7 | pad_left(3)("0"), "ab" | pad_right(4)("."), "abcde" | pad_left(3)(" ")]"# => r#"["007", "ab..", "abcde"]"#);
+make_fail_test!(pad_long_fill: r#""a" | pad_left(3)("ab")"#);
+make_test!(repeat: r#""ab" | repeat(3)"# => r#""ababab""#);
+make_fail_test!(repeat_too_long: r#""ab" | repeat(1e10)"#);
+make_test!(repeat_empty: r#""" | repeat(1e9)"# => r#""""#);
+make_fail_test!(substring_fraction: r#""abc" | substring(1.5)(2)"#);
+make_fail_test!(substring_negative: r#""abc" | substring(0)(-3)"#);
+make_fail_test!(pad_negative: r#""a" | pad_left(-1)(" ")"#);
+make_fail_test!(repeat_fraction: r#""ab" | repeat(2.5)"#);
+make_fail_test!(pad_too_long: r#""a" | pad_left(4e9)(" ")"#);
+make_test!(chars: r#"chars("hé!")"# => r#"["h", "é", "!"]"#);
+make_test!(reverse_string: r#"reverse("abc")"# => r#""cba""#);
+make_test!(format_array: r#"["web", 3] | format("{}-{}.example.com")"# => r#""web-3.example.com""#);
+make_test!(format_object: r#"{ env: "prod", index: 2 } | format("host-{env}-{index}")"# => r#""host-prod-2""#);
+make_test!(format_escape: r#"[1] | format("{{{}}}")"# => r#""{1}""#);
+make_fail_test!(format_missing_arg: r#"[1] | format("{} {}")"#);
+make_fail_test!(format_missing_key: r#"{} | format("{x}")"#);
+make_fail_test!(format_unclosed: r#"[1] | format("{")"#);
+make_test!(add_arrays: "[1, 2] + [3] + []" => "[1, 2, 3]");
+make_test!(add_objects: "{ a: 1, b: { x: 1 } } + { b: { y: 2 }, c: 3 }" => r#"{"a": 1, "b": {"y": 2}, "c": 3}"#);
+make_fail_test!(add_array_object: "[1] + { a: 1 }");
+make_test!(deep_merge: "deep_merge({ a: 1, b: { x: 1, z: [1] } })({ b: { y: 2, z: [2] }, c: 3 })" => r#"{"a": 1, "b": {"x": 1, "y": 2, "z": [2]}, "c": 3}"#);
+make_test!(deep_merge_replace: "deep_merge({ a: { x: 1 } })({ a: 2 })" => r#"{"a": 2}"#);
+make_fail_test!(deep_merge_not_objects: "deep_merge([1])([2])");
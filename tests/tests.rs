@@ -2,6 +2,9 @@
 mod common;
 
 make_test!(var: r"let x = 0; x" => "0");
+// the test helper compares numbers with the actual value
+make_test!(#[should_panic(expected = "assertion failed")] number_mismatch: "1 + 1" => "3");
+make_test!(block_comment: "/* a\n * longer comment */ 1 /**/ + /* two */ 2" => "3");
 make_test!(func: r"let f(x) = x + 1; f(2)" => "3");
 make_test!(func2: r"let f(x)(y) = x * y + x + y; f(3)(4)" => "19");
 make_test!(pipe: r"let f(x)(y) = x + y; 1 | f(2)" => "3");
@@ -44,3 +47,157 @@ make_test!(lambda_function2: "(x => y => x + y)(3)(4)" => "7");
 make_test!(empty_lambda: "(_ => x)" => "null");
 make_fail_test!(null_no_propagate_through_lambda: "(x => null?)().x");
 make_test!(lambda_allow_param_shadow_var: "let x = 3; (x => x + 2)(4)" => "6");
+make_test!(eq_numbers: "[1 == 1, 1 == 2, 1 != 2, 1 != 1]" => "[true, false, true, false]");
+make_test!(eq_strings: r#"["a" == "a", "a" == "b"]"# => "[true, false]");
+make_test!(eq_different_types: r#"[1 == "1", null == false, [] != {}]"# => "[false, false, true]");
+make_test!(eq_null_propagated: "let x = { y: null }; x?.y?.z == null" => "true");
+make_test!(eq_nested: r#"[{ a: [1, { b: "c" }] } == { a: [1, { b: "c" }] }, [1, [2]] == [1, [3]]]"# => "[true, false]");
+make_test!(eq_object_keys: "[{ a: 1 } == { a: 1, b: 2 }, { a: 1 } == { b: 1 }]" => "[false, false]");
+make_fail_test!(eq_functions: "let f(x) = x; f == f");
+make_test!(eq_function_other_type: "let f(x) = x; f == null" => "false");
+make_test!(cmp_numbers: "[1 < 2, 2 < 1, 1 <= 1, 2 > 1, 1 > 2, 1 >= 1]" => "[true, false, true, true, false, true]");
+make_test!(cmp_strings: r#"["abc" < "abd", "ab" < "abc", "b" > "abc"]"# => "[true, true, true]");
+make_test!(cmp_arrays: "[[1, 2] < [1, 3], [1, 2] < [1, 2, 0], [2] > [1, 9], [1] <= [1]]" => "[true, true, true, true]");
+make_test!(cmp_booleans: "[false < true, true > false, true <= true]" => "[true, true, true]");
+make_test!(cmp_precedence: "1 + 1 == 2" => "true");
+make_fail_test!(cmp_different_types: r#"1 < "2""#);
+make_fail_test!(cmp_objects: "{} < {}");
+make_test!(if_then: "if 1 < 2 then 3 else 4 end" => "3");
+make_test!(if_else: "if 1 > 2 then 3 else 4 end" => "4");
+make_test!(if_no_else: "if false then 3 end" => "null");
+make_test!(if_elif: "let f(x) = if x < 0 then -1 elif x == 0 then 0 else 1 end; [f(-5), f(0), f(5)]" => "[-1, 0, 1]");
+make_test!(if_lazy_branches: "if true then 1 else y end" => "1");
+make_test!(if_lazy_conditions: "if true then 1 elif y then 2 end" => "1");
+make_test!(if_in_expr: "1 + if true then 2 else 3 end * 2" => "5");
+make_test!(if_nested: "if true then if false then 1 else 2 end else 3 end" => "2");
+make_fail_test!(if_not_bool: "if 1 then 2 end");
+make_fail_test!(if_null_cond: "if null then 2 end");
+make_fail_test!(null_no_propagate_through_if: "(if true then null? end).x");
+make_parsefail_test!(if_missing_end: "if true then 1 else 2");
+make_test!(match_literal: r#"let f(x) = match x case 1 then "one" case "two" then 2 case true then "yes" case null then "none" case _ then "other" end; [f(1), f("two"), f(true), f(null), f(3)]"# => r#"["one", 2, "yes", "none", "other"]"#);
+make_test!(match_binding: "match 3 case x then x + 1 end" => "4");
+make_test!(match_type: r#"let f(x) = match x case n: number then n * 2 case s: string then s case _: array then "array" end; [f(2), f("a"), f([])]"# => r#"[4, "a", "array"]"#);
+make_test!(match_array: "match [1, 2, 3] case [a] then a case [a, b, c] then a + b + c end" => "6");
+make_test!(match_array_rest: "match [1, 2, 3] case [a, ...rest] then [a, rest] end" => "[1, [2, 3]]");
+make_test!(match_array_rest_empty: "match [1] case [a, ...rest] then rest end" => "[]");
+make_test!(match_array_rest_too_short: "match [] case [a, ...rest] then 1 case [] then 2 end" => "2");
+make_test!(match_object: r#"match { name: "web", port: 80, tls: false } case { name, port: p } then [name, p] end"# => r#"["web", 80]"#);
+make_test!(match_object_missing_key: r#"match { name: "web" } case { name, port } then 1 case { name } then 2 end"# => "2");
+make_test!(match_object_rest: r#"match { a: 1, b: 2, c: 3 } case { a, ...rest } then rest end"# => r#"{"b": 2, "c": 3}"#);
+make_test!(match_object_nested: r#"match { spec: { ports: [{ port: 80 }] } } case { spec: { ports: [{ port }] } } then port end"# => "80");
+make_test!(match_guard: "let f(x) = match x case n if n < 0 then 0 - n case n then n end; [f(-2), f(3)]" => "[2, 3]");
+make_test!(match_scope: "let x = 1; match 2 case x then x end + x" => "3");
+make_test!(match_lazy_arms: "match 1 case 1 then 2 case 2 then y end" => "2");
+make_fail_test!(match_no_case: "match 1 case 2 then 3 end");
+make_fail_test!(match_duplicate_binding: "match [1, 2] case [x, x] then x end");
+make_fail_test!(match_unknown_type: "match 1 case x: integer then x end");
+make_parsefail_test!(match_no_cases: "match 1 end");
+make_test!(arg_type_builtin: "let f(x: number) = x + 1; f(2)" => "3");
+make_fail_test!(arg_type_builtin_mismatch: r#"let f(x: number) = x; f("2")"#);
+make_test!(arg_type_name: r#"let f(x: "string") = x; f("a")"# => r#""a""#);
+make_test!(arg_type_null: "let f(x: null) = 1; f()" => "1");
+make_fail_test!(arg_type_nested_mismatch: r#"let f(x: number)(y: string) = x; f(1)(2)"#);
+make_test!(arg_type_predicate: "let positive(x) = x > 0; let f(x: positive) = x; f(3)" => "3");
+make_fail_test!(arg_type_predicate_mismatch: "let positive(x) = x > 0; let f(x: positive) = x; f(0)");
+make_test!(arg_type_array: "let f(x: [number]) = x; f([1, 2])" => "[1, 2]");
+make_fail_test!(arg_type_array_mismatch: r#"let f(x: [number]) = x; f([1, "2"])"#);
+make_test!(arg_type_object: r#"let f(x: { name: string, port: number }) = x.port; f({ name: "web", port: 80, tls: true })"# => "80");
+make_fail_test!(arg_type_object_missing_key: r#"let f(x: { name: string, port: number }) = x.port; f({ name: "web" })"#);
+make_fail_test!(arg_type_unknown_name: r#"let f(x: "integer") = x; 1"#);
+make_fail_test!(arg_type_not_a_type: "let f(x: 3) = x; 1");
+make_test!(type_as_predicate: r#"[number(1), number("1"), string("1")]"# => "[true, false, true]");
+
+#[test]
+fn arg_type_error() {
+    let err = jjay::run_script(r#"let f(x)(port: number) = port; f(1)("80")"#).unwrap_err();
+    match err.root_cause() {
+        jjay::ScriptError::InvalidArgumentType {
+            function,
+            arg,
+            expected,
+            actual,
+        } => {
+            assert_eq!(function, "f");
+            assert_eq!(arg, "port");
+            assert_eq!(expected, "Number");
+            assert_eq!(*actual, jjay::ValueType::String);
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
+make_test!(template: r#"let env = "prod"; let index = 3; `host-${env}-${index}`"# => r#""host-prod-3""#);
+make_test!(template_empty: "``" => r#""""#);
+make_test!(template_exprs: "`${1 + 2} ${ [1, 2][1] } ${true} ${null}`" => r#""3 2 true null""#);
+make_test!(template_nested: r#"`a${ `b${ "c" }` }`"# => r#""abc""#);
+make_test!(template_escapes: r#"`\` \${x} \n é 😀 "`"# => r#""` ${x} \n é 😀 \"""#);
+make_test!(template_unicode_escapes: r#"`\u00e9\uD83D\uDE00`"# => r#""é😀""#);
+make_test!(template_multiline: "`a\nb`" => r#""a\nb""#);
+make_test!(template_dollar: "`$5 {x}`" => r#""$5 {x}""#);
+make_fail_test!(template_not_convertible: "`x${[1]}`");
+make_parsefail_test!(template_unclosed_placeholder: "`x${1`");
+
+#[test]
+fn template_error_position() {
+    match jjay::run_script("let x = [1];\n`a ${x}`") {
+        Err(jjay::ScriptError::At(span, err)) => {
+            assert_eq!((span.line, span.column), (2, 4));
+            assert!(matches!(
+                *err,
+                jjay::ScriptError::NotStringConvertible(jjay::ValueType::Array)
+            ));
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn error_render() {
+    let source = "let x = 1;\n{ a: x + true }";
+    let err = jjay::run_script(source).unwrap_err();
+    assert_eq!(
+        err.render(Some(source)),
+        "cannot add values of types: Number, Boolean\n --> 2:6\n  |\n2 | { a: x + true }\n  |      ^^^^^^^^"
+    );
+}
+
+#[test]
+fn error_call_stack() {
+    let source = "let add_y(x) = x + y;\nlet twice(f)(x) = f(f(x));\ntwice(add_y)(1)";
+    let err = jjay::run_script(source).unwrap_err();
+    let stack = err
+        .call_stack()
+        .into_iter()
+        .map(|frame| {
+            let span = frame.span.unwrap();
+            (frame.to_string(), span.line, span.column)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        stack,
+        vec![
+            ("add_y(x = 1.0) at line 2, column 21".to_string(), 2, 21),
+            ("twice(x = 1.0) at line 3, column 1".to_string(), 3, 1),
+        ]
+    );
+    assert!(matches!(
+        err.root_cause(),
+        jjay::ScriptError::VariableNotFound(name) if name == "y"
+    ));
+}
+
+#[test]
+fn error_call_stack_lambda() {
+    let err = jjay::run_script("[1, 2] | map((x => x.foo))").unwrap_err();
+    let stack = err.call_stack();
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack[0].function, "<lambda>");
+    assert_eq!(stack[0].args, "x = 1.0");
+}
+
+#[test]
+fn repl_input() {
+    let (scope, value) = jjay::run_repl_input("let x = 2;", jjay::Scope::new_default()).unwrap();
+    assert!(value.is_none());
+    let (_, value) = jjay::run_repl_input("x * 3", scope).unwrap();
+    assert_eq!(value.unwrap().to_json().unwrap(), 6.0);
+}
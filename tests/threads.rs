@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use std::thread;
+
+use jjay::*;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn threads_auto_traits() {
+    assert_send_sync::<Value>();
+    assert_send_sync::<Scope>();
+    assert_send_sync::<CompiledScript>();
+    assert_send_sync::<Importer>();
+    assert_send_sync::<ScriptError>();
+}
+
+#[test]
+fn threads_shared_scope() {
+    // functions defined in scripts and native functions are both shared between the threads
+    let scope = Scope::new_default();
+    let scope = scope
+        .clone()
+        .set_nofail(
+            "triple",
+            run_script_with("let f(x) = x * 3; f", scope).unwrap(),
+        )
+        .set_nofail(
+            "offset",
+            Value::Function(Function::new(|_, arg| match arg {
+                Value::Number(n) => Ok(Value::Number(n + 0.5)),
+                _ => Err(ScriptError::Other("expected a number".to_string())),
+            })),
+        );
+    let scope = Arc::new(scope);
+
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let scope = scope.clone();
+            thread::spawn(move || {
+                let scope = scope.inherit().set_nofail("i", Value::Number(i as f64));
+                run_script_with("i | triple | offset", scope).unwrap()
+            })
+        })
+        .collect();
+    for (i, handle) in handles.into_iter().enumerate() {
+        let value = handle.join().unwrap();
+        assert_eq!(
+            value.to_json().unwrap(),
+            serde_json::json!(i as f64 * 3.0 + 0.5)
+        );
+    }
+}
+
+#[test]
+fn threads_shared_importer() {
+    // files being imported by one thread are not an import cycle for the others
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/import_data/main.jjay");
+    let importer = Importer::new();
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let importer = importer.clone();
+            let path = path.clone();
+            thread::spawn(move || {
+                for _ in 0..20 {
+                    importer.run_file(&path, Scope::new_default()).unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,50 @@
+use jjay::*;
+
+fn to_toml(source: &str) -> ScriptResult<String> {
+    jjay::run_script(source).and_then(|value| value.to_toml())
+}
+
+#[test]
+fn toml_values() {
+    assert_eq!(
+        to_toml(r#"{ name: "web \"1\"", port: 80, ratio: 0.5, tls: true, tags: ["a", "b"], "x.y": [] }"#)
+            .unwrap(),
+        "name = \"web \\\"1\\\"\"\nport = 80\nratio = 0.5\ntags = [\"a\", \"b\"]\ntls = true\n\"x.y\" = []\n"
+    );
+}
+
+#[test]
+fn toml_tables() {
+    assert_eq!(
+        to_toml(
+            r#"{ server: { host: "h", tls: { cert: "c" } }, title: "t", points: [[{ x: 1 }]] }"#
+        )
+        .unwrap(),
+        "points = [[{ x = 1 }]]\ntitle = \"t\"\n\n[server]\nhost = \"h\"\n\n[server.tls]\ncert = \"c\"\n"
+    );
+}
+
+#[test]
+fn toml_arrays_of_tables() {
+    assert_eq!(
+        to_toml(r#"{ routes: [{ path: "/", backend: { name: "b" } }, { path: "/api" }] }"#).unwrap(),
+        "[[routes]]\npath = \"/\"\n\n[routes.backend]\nname = \"b\"\n\n[[routes]]\npath = \"/api\"\n"
+    );
+}
+
+#[test]
+fn toml_errors() {
+    let message = |source| to_toml(source).unwrap_err().to_string();
+    assert_eq!(
+        message("{ spec: { ports: [{ port: 1 }, { port: null }] } }"),
+        "Cannot write TOML at spec.ports[1].port: null is not supported"
+    );
+    assert_eq!(
+        message(r#"{ a: [1, "x"] }"#),
+        "Cannot write TOML at a: arrays must not mix types, found Number and String"
+    );
+    assert_eq!(
+        message("[1]"),
+        "Cannot write TOML: the top-level value must be an object, found Array"
+    );
+}
@@ -0,0 +1,90 @@
+use jjay::*;
+
+fn to_yaml(source: &str, options: YamlOptions) -> String {
+    jjay::run_script(source)
+        .and_then(|value| value.to_yaml(options))
+        .unwrap_or_else(|err| panic!("test failure:\n{}", err))
+}
+
+#[test]
+fn yaml_block() {
+    assert_eq!(
+        to_yaml(
+            r#"{ name: "web", ports: [80, 443], env: { debug: false }, empty: [], none: null }"#,
+            YamlOptions::default()
+        ),
+        "empty: []\nenv:\n  debug: false\nname: web\nnone: null\nports:\n  - 80\n  - 443\n"
+    );
+    assert_eq!(
+        to_yaml("[{ a: 1, b: [1.5] }, [2, 3], {}]", YamlOptions::default()),
+        "- a: 1\n  b:\n    - 1.5\n- - 2\n  - 3\n- {}\n"
+    );
+}
+
+#[test]
+fn yaml_flow() {
+    let options = YamlOptions {
+        flow: true,
+        ..YamlOptions::default()
+    };
+    assert_eq!(
+        to_yaml(r#"{ a: [1, "b, c"], d: {} }"#, options),
+        "{a: [1, \"b, c\"], d: {}}\n"
+    );
+}
+
+#[test]
+fn yaml_quoting() {
+    assert_eq!(
+        to_yaml(
+            r#"["yes", "No", "null", "~", "1.0", "2024-01-01", "", " x", "a: b", "a #b", "- x", "*x", "plain text"]"#,
+            YamlOptions::default()
+        ),
+        concat!(
+            "- \"yes\"\n- \"No\"\n- \"null\"\n- \"~\"\n- \"1.0\"\n- \"2024-01-01\"\n- \"\"\n",
+            "- \" x\"\n- \"a: b\"\n- \"a #b\"\n- \"- x\"\n- \"*x\"\n- plain text\n"
+        )
+    );
+    assert_eq!(
+        to_yaml(r#"{ "yes": 1, on: 2 }"#, YamlOptions::default()),
+        "\"on\": 2\n\"yes\": 1\n"
+    );
+}
+
+#[test]
+fn yaml_numbers() {
+    assert_eq!(
+        to_yaml("[1, 2.5, 0 - 3, 1e20, 0.000001]", YamlOptions::default()),
+        "- 1\n- 2.5\n- -3\n- 1.0e+20\n- 1.0e-6\n"
+    );
+}
+
+#[test]
+fn yaml_block_literals() {
+    assert_eq!(
+        to_yaml(
+            r#"{ clip: "a\nb\n", strip: "a\nb", keep: "a\n\n", indented: "  a\nb", single: "\n" }"#,
+            YamlOptions::default()
+        ),
+        concat!(
+            "clip: |\n  a\n  b\n",
+            "indented: \"  a\\nb\"\n",
+            "keep: |+\n  a\n\n",
+            "single: \"\\n\"\n",
+            "strip: |-\n  a\n  b\n",
+        )
+    );
+}
+
+#[test]
+fn yaml_multi_document() {
+    let options = YamlOptions {
+        multi_document: true,
+        ..YamlOptions::default()
+    };
+    assert_eq!(
+        to_yaml("[{ kind: \"Service\" }, { kind: \"Deployment\" }]", options),
+        "---\nkind: Service\n---\nkind: Deployment\n"
+    );
+    assert_eq!(to_yaml("{ a: 1 }", options), "a: 1\n");
+}